@@ -0,0 +1,184 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use image::ImageFormat;
+
+use crate::calibration::Calibration;
+use crate::game_logic::{count_queens_solutions, queens};
+use crate::image_processor::process_image_with_calibration;
+
+// What happened to one screenshot of a batch
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub path: PathBuf,
+    // n of the n*n board, None when the board couldn't be read
+    pub grid_size: Option<usize>,
+    pub solution: Option<Vec<usize>>,
+    // None when there's no solution to compare against
+    pub unique: Option<bool>,
+    pub read_time: Duration,
+    pub solve_time: Duration,
+    pub error: Option<String>,
+}
+
+// Reads and solves every image under `dir` (subdirectories too), in path
+// order so two runs over the same collection line up
+pub fn process_directory(dir: &str) -> Result<Vec<BatchResult>, Box<dyn Error>> {
+    process_directory_with_calibration(dir, Calibration::default())
+}
+
+pub fn process_directory_with_calibration(
+    dir: &str,
+    calibration: Calibration,
+) -> Result<Vec<BatchResult>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    collect_images(Path::new(dir), &mut paths)?;
+    paths.sort();
+    println!("Found {} images in {}", paths.len(), dir);
+
+    Ok(paths
+        .into_iter()
+        .map(|path| process_one(&path, &calibration))
+        .collect())
+}
+
+fn collect_images(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_images(&path, paths)?;
+        } else if ImageFormat::from_path(&path).is_ok() {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn process_one(path: &Path, calibration: &Calibration) -> BatchResult {
+    println!("Processing {}", path.display());
+    let mut result = BatchResult {
+        path: path.to_path_buf(),
+        grid_size: None,
+        solution: None,
+        unique: None,
+        read_time: Duration::ZERO,
+        solve_time: Duration::ZERO,
+        error: None,
+    };
+
+    let start = Instant::now();
+    let board = process_image_with_calibration(&path.to_string_lossy(), calibration.clone());
+    result.read_time = start.elapsed();
+    let board = match board {
+        Ok(board) => board,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.grid_size = Some(board.len());
+
+    let start = Instant::now();
+    match queens(&board) {
+        Ok(solution) => {
+            result.solution = Some(solution);
+            result.unique = Some(count_queens_solutions(&board, 2) == 1);
+        }
+        Err(e) => result.error = Some(e),
+    }
+    result.solve_time = start.elapsed();
+    result
+}
+
+// Writes the report as JSON when `path` ends in .json, as CSV otherwise
+pub fn write_batch_report(results: &[BatchResult], path: &str) -> Result<(), Box<dyn Error>> {
+    let report = if path.to_lowercase().ends_with(".json") {
+        batch_report_json(results)
+    } else {
+        batch_report_csv(results)
+    };
+    std::fs::write(path, report)?;
+    Ok(())
+}
+
+// One line per image, the solution as space separated cell indexes
+pub fn batch_report_csv(results: &[BatchResult]) -> String {
+    let mut csv = String::from("path,grid_size,found,unique,solution,read_ms,solve_ms,error\n");
+    for result in results {
+        let fields = [
+            result.path.display().to_string(),
+            result.grid_size.map(|n| n.to_string()).unwrap_or_default(),
+            result.solution.is_some().to_string(),
+            result
+                .unique
+                .map(|unique| unique.to_string())
+                .unwrap_or_default(),
+            result
+                .solution
+                .as_ref()
+                .map(|cells| {
+                    cells
+                        .iter()
+                        .map(|cell| cell.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .unwrap_or_default(),
+            format!("{:.1}", result.read_time.as_secs_f64() * 1000.0),
+            format!("{:.1}", result.solve_time.as_secs_f64() * 1000.0),
+            result.error.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv += &line.join(",");
+        csv.push('\n');
+    }
+    csv
+}
+
+pub fn batch_report_json(results: &[BatchResult]) -> String {
+    let entries: Vec<String> = results
+        .iter()
+        .map(|result| {
+            let or_null = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+            format!(
+                "  {{\"path\": {}, \"grid_size\": {}, \"found\": {}, \"unique\": {}, \"solution\": {}, \"read_ms\": {:.1}, \"solve_ms\": {:.1}, \"error\": {}}}",
+                json_string(&result.path.display().to_string()),
+                or_null(result.grid_size.map(|n| n.to_string())),
+                result.solution.is_some(),
+                or_null(result.unique.map(|unique| unique.to_string())),
+                or_null(result.solution.as_ref().map(|cells| format!("{:?}", cells))),
+                result.read_time.as_secs_f64() * 1000.0,
+                result.solve_time.as_secs_f64() * 1000.0,
+                or_null(result.error.as_deref().map(json_string)),
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", entries.join(",\n"))
+}
+
+// quoted when it has to be, quotes inside doubled
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            '\r' => escaped += "\\r",
+            '\t' => escaped += "\\t",
+            c if (c as u32) < 0x20 => escaped += &format!("\\u{:04x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
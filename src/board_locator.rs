@@ -0,0 +1,243 @@
+use image::{DynamicImage, GenericImageView, GrayImage};
+use rayon::prelude::*;
+
+// a pixel darker than this is part of the grid lines (or text, icons, ...),
+// on a dark page it's half the page's luminance instead
+const DARK_LUMA: u8 = 80;
+// how far from square the board's bounding box may be, as width/height ratio
+const MAX_ASPECT_DIFF: f32 = 0.1;
+// same for photos, where the perspective squashes the board
+const MAX_PHOTO_ASPECT_DIFF: f32 = 0.5;
+// the grid is mostly cells, a dark blob filling more than this of its
+// bounding box is a photo or a dark toolbar, not a grid
+const MAX_FILL_RATIO: f32 = 0.5;
+
+// where the board is in a bigger screenshot, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Finds the board in a full desktop or mobile screenshot. The grid lines and
+// the thick outer border are all connected, so the board is the biggest group
+// of connected dark pixels whose bounding box is (about) square and mostly
+// empty. Returns None when nothing in the image looks like a grid.
+pub fn locate_board(image: &DynamicImage) -> Option<BoardRegion> {
+    largest_grid(image, MAX_ASPECT_DIFF).map(|grid| grid.region)
+}
+
+// Finds the four outer corners of the board's border, in the order top left,
+// top right, bottom right, bottom left. Unlike `locate_board` the board doesn't
+// have to be upright, a photo taken at an angle turns the square into some
+// quadrilateral, which is why the aspect ratio check is a lot looser.
+pub fn find_grid_corners(image: &DynamicImage) -> Option<[(f32, f32); 4]> {
+    largest_grid(image, MAX_PHOTO_ASPECT_DIFF).map(|grid| grid.corners)
+}
+
+struct GridComponent {
+    region: BoardRegion,
+    // the pixels furthest towards each corner, see `find_grid_corners`
+    corners: [(f32, f32); 4],
+}
+
+fn largest_grid(image: &DynamicImage, max_aspect_diff: f32) -> Option<GridComponent> {
+    let (width, height) = image.dimensions();
+    let luma = luma_buffer(image);
+    // with the dark theme the page is nearly as dark as the lines, only the
+    // lines themselves should count or the board is part of the page
+    let dark_luma = DARK_LUMA.min(frame_luma(&luma)? / 2);
+    let mut dark: Vec<bool> = luma
+        .into_raw()
+        .into_iter()
+        .map(|value| value < dark_luma)
+        .collect();
+    // a board smaller than this can't be read anyway
+    let min_size = (width.min(height) / 6).max(50);
+
+    let mut best: Option<GridComponent> = None;
+    let mut stack = Vec::new();
+
+    for start in 0..dark.len() {
+        // a pixel is taken out of `dark` once it's part of a component
+        if !dark[start] {
+            continue;
+        }
+
+        // flood fill the component, keeping track of its bounding box and of
+        // the pixels with the smallest/largest x + y and x - y, which are the
+        // corners of a (possibly tilted) square
+        dark[start] = false;
+        stack.push(start);
+        let (mut min_x, mut min_y) = (u32::MAX, u32::MAX);
+        let (mut max_x, mut max_y) = (0, 0);
+        let mut extremes = [(i64::MAX, 0u32, 0u32); 4];
+        let mut count: u64 = 0;
+        while let Some(idx) = stack.pop() {
+            let (x, y) = ((idx as u32) % width, (idx as u32) / width);
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+            count += 1;
+
+            let (sx, sy) = (x as i64, y as i64);
+            // scores to minimize for top left, top right, bottom right, bottom left
+            for (extreme, score) in extremes
+                .iter_mut()
+                .zip([sx + sy, sy - sx, -sx - sy, sx - sy])
+            {
+                if score < extreme.0 {
+                    *extreme = (score, x, y);
+                }
+            }
+
+            let neighbours = [
+                (x > 0).then(|| idx - 1),
+                (x + 1 < width).then(|| idx + 1),
+                (y > 0).then(|| idx - width as usize),
+                (y + 1 < height).then(|| idx + width as usize),
+            ];
+            for next in neighbours.into_iter().flatten() {
+                if dark[next] {
+                    dark[next] = false;
+                    stack.push(next);
+                }
+            }
+        }
+
+        let region = BoardRegion {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        };
+        if region.width < min_size || region.height < min_size {
+            continue;
+        }
+        let aspect = region.width as f32 / region.height as f32;
+        if (aspect - 1.0).abs() > max_aspect_diff {
+            continue;
+        }
+        let fill = count as f32 / (region.width as f32 * region.height as f32);
+        if fill > MAX_FILL_RATIO {
+            continue;
+        }
+        if best.as_ref().is_none_or(|best| {
+            region.width * region.height > best.region.width * best.region.height
+        }) {
+            best = Some(GridComponent {
+                region,
+                corners: extremes.map(|(_, x, y)| (x as f32, y as f32)),
+            });
+        }
+    }
+
+    best
+}
+
+// Luminance of the page around the board, the median of the image's outermost
+// pixels. For an image that's cropped right at the board this is the outer
+// border instead. None for an empty image, it has no outermost pixels.
+pub fn background_luma(image: &DynamicImage) -> Option<u8> {
+    frame_luma(&luma_buffer(image))
+}
+
+// `background_luma` for an image that's already converted
+pub(crate) fn frame_luma(luma: &GrayImage) -> Option<u8> {
+    let (width, height) = luma.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let mut frame: Vec<u8> = Vec::with_capacity(2 * (width + height) as usize);
+    for x in 0..width {
+        frame.push(luma.get_pixel(x, 0)[0]);
+        frame.push(luma.get_pixel(x, height - 1)[0]);
+    }
+    for y in 0..height {
+        frame.push(luma.get_pixel(0, y)[0]);
+        frame.push(luma.get_pixel(width - 1, y)[0]);
+    }
+    frame.sort_unstable();
+    Some(frame[frame.len() / 2])
+}
+
+// `image.to_luma8()` with the same Rec. 709 weights (in integers, so a pixel
+// right between two values may round the other way), but straight from the
+// raw bytes and a row at a time in parallel for the formats screenshots come
+// in, which is a lot faster on a 4K screenshot
+pub(crate) fn luma_buffer(image: &DynamicImage) -> GrayImage {
+    let (width, height) = image.dimensions();
+    let channels = match image {
+        _ if width == 0 => return image.to_luma8(),
+        DynamicImage::ImageRgb8(_) => 3,
+        DynamicImage::ImageRgba8(_) => 4,
+        _ => return image.to_luma8(),
+    };
+    let raw = image.as_bytes();
+    let mut luma = vec![0u8; raw.len() / channels];
+    luma.par_chunks_mut(width as usize)
+        .zip(raw.par_chunks(width as usize * channels))
+        .for_each(|(luma_row, row)| match channels {
+            3 => row_luma::<3>(row, luma_row),
+            _ => row_luma::<4>(row, luma_row),
+        });
+    GrayImage::from_raw(width, height, luma).expect("one luma byte per pixel")
+}
+
+// the channel count as a constant lets the compiler vectorize the loop, the
+// weights are 0.2126, 0.7152 and 0.0722 in 1/65536ths so they add up to one
+fn row_luma<const CHANNELS: usize>(row: &[u8], luma_row: &mut [u8]) {
+    for (value, pixel) in luma_row.iter_mut().zip(row.chunks_exact(CHANNELS)) {
+        let weighted = 13933 * pixel[0] as u32 + 46871 * pixel[1] as u32 + 4732 * pixel[2] as u32;
+        *value = ((weighted + 32768) >> 16) as u8;
+    }
+}
+
+// Crops the screenshot to the board, keeping a small margin around the outer
+// border so the line detection sees where the border ends
+pub fn crop_to_board(image: &DynamicImage, region: BoardRegion) -> DynamicImage {
+    let bounds = crop_bounds(image, region);
+    image.crop_imm(bounds.x, bounds.y, bounds.width, bounds.height)
+}
+
+// the part of the screenshot `crop_to_board` keeps, margin included
+pub fn crop_bounds(image: &DynamicImage, region: BoardRegion) -> BoardRegion {
+    let (width, height) = image.dimensions();
+    let margin = (region.width.max(region.height) / 50).max(4);
+    let x = region.x.saturating_sub(margin);
+    let y = region.y.saturating_sub(margin);
+    let right = (region.x + region.width + margin).min(width);
+    let bottom = (region.y + region.height + margin).min(height);
+    BoardRegion {
+        x,
+        y,
+        width: right - x,
+        height: bottom - y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luma_buffer_is_to_luma8_give_or_take_rounding() {
+        for path in ["imagez.png", "image0.png"] {
+            let image = image::open(path).unwrap();
+            for image in [DynamicImage::ImageRgb8(image.to_rgb8()), image.clone()] {
+                let (fast, slow) = (luma_buffer(&image), image.to_luma8());
+                assert_eq!(fast.dimensions(), slow.dimensions());
+                let off = fast
+                    .as_raw()
+                    .iter()
+                    .zip(slow.as_raw())
+                    .map(|(a, b)| a.abs_diff(*b))
+                    .max();
+                assert!(off <= Some(1), "{} {:?}", path, off);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::error::Error;
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+
+use crate::game_logic::CellColor;
+use crate::image_processor::{COLOR_DEFINITIONS, CellMark, Theme};
+use crate::solution_overlay::draw_crown_marker;
+
+const LINE_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const CROSS_COLOR: Rgba<u8> = Rgba([70, 70, 70, 255]);
+const LIGHT_PAGE: Rgba<u8> = Rgba([243, 242, 239, 255]);
+const DARK_PAGE: Rgba<u8> = Rgba([27, 31, 35, 255]);
+
+// How `render_board` draws a board, the defaults look like a desktop
+// screenshot of the light theme
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    // cell side in pixels, before scaling
+    pub cell_size: u32,
+    // the lines between cells of the same region
+    pub line_width: u32,
+    // the lines between two regions
+    pub region_width: u32,
+    // the frame around the whole board
+    pub border_width: u32,
+    // page around the board, on every side
+    pub margin: u32,
+    // the finished picture is resized by this, like a zoomed or a retina
+    // screenshot
+    pub scale: f32,
+    // Some(quality) encodes as JPEG (1-100) in `encode_board`, None as PNG
+    pub jpeg_quality: Option<u8>,
+    // only the page around the board, the cells keep the light palette, see
+    // `Theme`
+    pub theme: Theme,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 72,
+            line_width: 1,
+            region_width: 3,
+            border_width: 6,
+            margin: 20,
+            scale: 1.0,
+            jpeg_quality: None,
+            theme: Theme::Light,
+        }
+    }
+}
+
+// Draws `colors` the way LinkedIn does: the palette colors on the theme's page, thin
+// black lines between cells and thick ones between regions, with the crowns
+// and X marks of `marks` on top. What went in is exactly what `process_image`
+// and `BoardImage::get_board_state` should read back.
+pub fn render_board(
+    colors: &[Vec<CellColor>],
+    marks: Option<&[Vec<CellMark>]>,
+    options: &RenderOptions,
+) -> DynamicImage {
+    let n = colors.len() as u32;
+    let cell = options.cell_size;
+    let board = n * cell + 2 * options.border_width;
+    let size = board + 2 * options.margin;
+    let page = match options.theme {
+        Theme::Light => LIGHT_PAGE,
+        Theme::Dark => DARK_PAGE,
+    };
+    let mut image = RgbaImage::from_pixel(size, size, page);
+
+    // the border, then the cells inside it
+    let start = options.margin;
+    fill(&mut image, start, start, board, board, LINE_COLOR);
+    let inner = start + options.border_width;
+    for row in 0..n {
+        for col in 0..n {
+            let color = colors[row as usize][col as usize];
+            let (r, g, b) = COLOR_DEFINITIONS
+                .iter()
+                .find(|(_, entry)| *entry == color)
+                .map(|(rgb, _)| *rgb)
+                .unwrap_or((255, 255, 255));
+            let (x, y) = (inner + col * cell, inner + row * cell);
+            fill(&mut image, x, y, cell, cell, Rgba([r, g, b, 255]));
+        }
+    }
+
+    // lines centered on the cell boundaries, thick where the colors change
+    for row in 0..n {
+        for col in 0..n {
+            let (x, y) = (inner + col * cell, inner + row * cell);
+            let color = colors[row as usize][col as usize];
+            if col + 1 < n {
+                let width = if colors[row as usize][col as usize + 1] == color {
+                    options.line_width
+                } else {
+                    options.region_width
+                };
+                fill(&mut image, x + cell - width / 2, y, width, cell, LINE_COLOR);
+            }
+            if row + 1 < n {
+                let width = if colors[row as usize + 1][col as usize] == color {
+                    options.line_width
+                } else {
+                    options.region_width
+                };
+                fill(&mut image, x, y + cell - width / 2, cell, width, LINE_COLOR);
+            }
+        }
+    }
+
+    if let Some(marks) = marks {
+        for row in 0..n {
+            for col in 0..n {
+                let (cx, cy) = (inner + col * cell + cell / 2, inner + row * cell + cell / 2);
+                match marks[row as usize][col as usize] {
+                    CellMark::Empty => {}
+                    CellMark::Queen => draw_crown_marker(&mut image, cx, cy, cell),
+                    CellMark::Cross => draw_cross(&mut image, cx, cy, cell),
+                }
+            }
+        }
+    }
+
+    let image = DynamicImage::ImageRgba8(image);
+    if (options.scale - 1.0).abs() < f32::EPSILON {
+        return image;
+    }
+    let scaled = ((size as f32 * options.scale).round() as u32).max(1);
+    image.resize_exact(scaled, scaled, FilterType::Triangle)
+}
+
+// `render_board` encoded as PNG, or as JPEG when `options.jpeg_quality` is set
+pub fn encode_board(
+    colors: &[Vec<CellColor>],
+    marks: Option<&[Vec<CellMark>]>,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let image = render_board(colors, marks, options);
+    let mut bytes = Cursor::new(Vec::new());
+    match options.jpeg_quality {
+        Some(quality) => {
+            let encoder = JpegEncoder::new_with_quality(&mut bytes, quality.clamp(1, 100));
+            // JPEG has no alpha channel
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?;
+        }
+        None => image.write_to(&mut bytes, ImageFormat::Png)?,
+    }
+    Ok(bytes.into_inner())
+}
+
+pub fn save_rendered_board(
+    colors: &[Vec<CellColor>],
+    marks: Option<&[Vec<CellMark>]>,
+    options: &RenderOptions,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, encode_board(colors, marks, options)?)?;
+    Ok(())
+}
+
+// the X of a cell the player ruled out, two short diagonal strokes
+fn draw_cross(image: &mut RgbaImage, cx: u32, cy: u32, cell_size: u32) {
+    let half = (cell_size / 10).max(2) as i32;
+    let thickness = (cell_size / 36).max(1) as i32;
+    for d in -half..=half {
+        for t in 0..thickness {
+            for (x, y) in [
+                (cx as i32 + d + t, cy as i32 + d),
+                (cx as i32 + d + t, cy as i32 - d),
+            ] {
+                if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+                    image.put_pixel(x as u32, y as u32, CROSS_COLOR);
+                }
+            }
+        }
+    }
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
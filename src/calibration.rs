@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::game_logic::CellColor;
+
+const MIN_GRID_SIZE: u32 = 6; // Minimum expected grid size
+const WINDOW_SIZE: u32 = 5; // Dark runs closer than this are merged into one line
+const SAMPLE_INSET: f32 = 0.15; // Samples stay this far (share of the cell) from the borders
+const SAMPLE_CENTER: f32 = 0.25; // and skip the middle out to this far from the cell center
+const CALIBRATION_DIR: &str = "calibrations"; // Where the named profiles are saved
+
+// The knobs of the image reading that depend on where the screenshot comes
+// from (device, DPI, browser zoom). Saved as a named profile so they can be
+// tuned without a rebuild, `QUEENS_CALIBRATION=retina cargo run -- image
+// board.png` reads with the profile saved as `calibrations/retina.txt`. The
+// library itself always reads with the defaults unless it's handed a profile
+// through one of the `_with_calibration` functions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub name: String,
+    // cell colors as this device renders them, they replace the built-in
+    // palette entries of the same color, colors not listed keep the built-in
+    // light palette value whatever the theme, the same as with
+    // `Calibration::default()`
+    pub palette: Vec<((u8, u8, u8), CellColor)>,
+    // the thickest grid line (the outer border) in pixels, 0 guesses it from
+    // the image size
+    pub line_thickness: u32,
+    // dark runs closer than this are merged into one line
+    pub window_size: u32,
+    // boards with fewer lines than this in either direction are rejected
+    pub min_grid_size: u32,
+    // the sample ring of a cell, as shares of the cell side: how far it stays
+    // from the borders and how far from the center it starts
+    pub sample_inset: f32,
+    pub sample_center: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            palette: Vec::new(),
+            line_thickness: 0,
+            window_size: WINDOW_SIZE,
+            min_grid_size: MIN_GRID_SIZE,
+            sample_inset: SAMPLE_INSET,
+            sample_center: SAMPLE_CENTER,
+        }
+    }
+}
+
+impl Calibration {
+    pub fn path(name: &str) -> PathBuf {
+        PathBuf::from(CALIBRATION_DIR).join(format!("{}.txt", name))
+    }
+
+    pub fn load_named(name: &str) -> Result<Self, Box<dyn Error>> {
+        Self::load(&Self::path(name).to_string_lossy())
+    }
+
+    // Saves to `calibrations/<name>.txt` and returns that path
+    pub fn save_named(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let path = Self::path(&self.name);
+        std::fs::create_dir_all(CALIBRATION_DIR)?;
+        self.save(&path.to_string_lossy())?;
+        Ok(path)
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    // One `key = value` per line, the palette as `color <name> = r g b`.
+    // Keys that are left out keep their default.
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# queens calibration profile\n");
+        text += &format!("name = {}\n", self.name);
+        text += &format!("line_thickness = {}\n", self.line_thickness);
+        text += &format!("window_size = {}\n", self.window_size);
+        text += &format!("min_grid_size = {}\n", self.min_grid_size);
+        text += &format!("sample_inset = {}\n", self.sample_inset);
+        text += &format!("sample_center = {}\n", self.sample_center);
+        for ((r, g, b), color) in &self.palette {
+            text += &format!("color {:?} = {} {} {}\n", color, r, g, b);
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut calibration = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(format!("Line {}: expected `key = value`", number + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid =
+                |e: &dyn std::fmt::Display| format!("Line {}: {}: {}", number + 1, key, e);
+
+            if let Some(color) = key.strip_prefix("color ") {
+                let color = color_by_name(color.trim()).ok_or(format!(
+                    "Line {}: unknown color {}",
+                    number + 1,
+                    color
+                ))?;
+                let channels = value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|part| !part.is_empty())
+                    .map(|part| part.parse::<u8>().map_err(|e| invalid(&e)))
+                    .collect::<Result<Vec<u8>, String>>()?;
+                let [r, g, b] = channels[..] else {
+                    return Err(invalid(&"expected three channels (r g b)"));
+                };
+                calibration.palette.retain(|(_, entry)| *entry != color);
+                calibration.palette.push(((r, g, b), color));
+                continue;
+            }
+
+            match key {
+                "name" => calibration.name = value.to_string(),
+                "line_thickness" => {
+                    calibration.line_thickness = value.parse().map_err(|e| invalid(&e))?
+                }
+                "window_size" => {
+                    calibration.window_size = value.parse().map_err(|e| invalid(&e))?
+                }
+                "min_grid_size" => {
+                    calibration.min_grid_size = value.parse().map_err(|e| invalid(&e))?
+                }
+                "sample_inset" => {
+                    calibration.sample_inset = value.parse().map_err(|e| invalid(&e))?
+                }
+                "sample_center" => {
+                    calibration.sample_center = value.parse().map_err(|e| invalid(&e))?
+                }
+                _ => return Err(format!("Line {}: unknown key {}", number + 1, key)),
+            }
+        }
+        calibration.validate()?;
+        Ok(calibration)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.window_size == 0 {
+            return Err("window_size has to be at least 1".to_string());
+        }
+        if self.min_grid_size < 2 {
+            return Err("min_grid_size has to be at least 2".to_string());
+        }
+        if !(0.0..0.5).contains(&self.sample_inset) {
+            return Err("sample_inset has to be between 0 and 0.5".to_string());
+        }
+        // the outermost ring has to survive, or a cell has no samples at all
+        if !(0.0..0.5 - self.sample_inset).contains(&self.sample_center) {
+            return Err(format!(
+                "sample_center has to be between 0 and {}",
+                0.5 - self.sample_inset
+            ));
+        }
+        Ok(())
+    }
+}
+
+// the names are the variant names, as `{:?}` prints them
+fn color_by_name(name: &str) -> Option<CellColor> {
+    CellColor::ALL
+        .into_iter()
+        .find(|color| format!("{:?}", color).eq_ignore_ascii_case(name))
+}
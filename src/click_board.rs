@@ -1,6 +1,8 @@
 use thirtyfour::By;
 use thirtyfour::prelude::*;
 
+use crate::open_browser::keep_browser_open;
+
 pub async fn click_solution_squares(
     driver: &WebDriver,
     solution_indices: &[usize],
@@ -22,7 +24,7 @@ pub async fn click_solution_squares(
         // Small delay between clicks to avoid overwhelming the browser
         // tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
-    tokio::time::sleep(tokio::time::Duration::from_secs(59)).await;
+    keep_browser_open().await;
 
     Ok(())
 }
@@ -1,12 +1,37 @@
 use std::collections::HashSet;
+use std::hash::Hash;
+
+// `T` is whatever tells the regions apart, the `CellColor` of the cells when
+// the board comes from the page, or a region id when it's read from the
+// borders of a screenshot
+pub fn queens<T: Copy + Eq + Hash>(board: &Vec<Vec<T>>) -> Result<Vec<usize>, String> {
+    queens_with_placed(board, &[])
+}
+
+// Finishes a game that's already in progress, the queens in `placed` (row, col)
+// stay where they are. X marks aren't passed in, they're only the player's
+// notes and might be wrong.
+pub fn queens_with_placed<T: Copy + Eq + Hash>(
+    board: &Vec<Vec<T>>,
+    placed: &[(usize, usize)],
+) -> Result<Vec<usize>, String> {
+    let mut fixed: Vec<Option<usize>> = vec![None; board.len()];
+    for &(row, col) in placed {
+        if row >= board.len() || col >= board.len() {
+            return Err(format!("Placed queen ({}, {}) is off the board", row, col));
+        }
+        if fixed[row].is_some_and(|other| other != col) {
+            return Err(format!("Two queens placed on row {}", row));
+        }
+        fixed[row] = Some(col);
+    }
 
-pub fn queens(board: &Vec<Vec<CellColor>>) -> Result<Vec<usize>, String> {
     let mut status: Vec<Vec<Option<Status>>> = vec![vec![None; board.len()]; board.len()];
     // the game uses backtrack to solve the game, it doesn't rely on marking
     // cells with (can not be a queen) by applying the rules, it's just a
     // backtracking brute-force solution
-    let mut colors: HashSet<CellColor> = HashSet::with_capacity(board.len());
-    if let Some(result) = backtrack(&board, &mut status, 0, board.len(), &mut colors) {
+    let mut colors: HashSet<T> = HashSet::with_capacity(board.len());
+    if let Some(result) = backtrack(board, &mut status, 0, board.len(), &mut colors, &fixed) {
         return Ok(result);
     }
     for row in &status {
@@ -15,12 +40,50 @@ pub fn queens(board: &Vec<Vec<CellColor>>) -> Result<Vec<usize>, String> {
     Err("No solution found".to_string())
 }
 
-fn backtrack(
-    board: &Vec<Vec<CellColor>>,
+// How many solutions the board has, counting stops at `limit` (a limit of 2
+// is enough to tell whether the solution is unique)
+pub fn count_queens_solutions<T: Copy + Eq + Hash>(board: &Vec<Vec<T>>, limit: usize) -> usize {
+    let mut status: Vec<Vec<Option<Status>>> = vec![vec![None; board.len()]; board.len()];
+    let mut colors: HashSet<T> = HashSet::with_capacity(board.len());
+    count_solutions(board, &mut status, 0, board.len(), &mut colors, limit)
+}
+
+// same search as `backtrack`, but keeps going after the first solution
+fn count_solutions<T: Copy + Eq + Hash>(
+    board: &Vec<Vec<T>>,
     status: &mut Vec<Vec<Option<Status>>>,
     row: usize,
     n: usize,
-    colors: &mut HashSet<CellColor>,
+    colors: &mut HashSet<T>,
+    limit: usize,
+) -> usize {
+    if row == n {
+        return 1;
+    }
+
+    let mut found = 0;
+    for col in 0..n {
+        if found >= limit {
+            break;
+        }
+        if is_valid(board, status, row, col, n, colors) {
+            status[row][col] = Some(Status::Queen);
+            colors.insert(board[row][col]);
+            found += count_solutions(board, status, row + 1, n, colors, limit - found);
+            status[row][col] = None;
+            colors.remove(&board[row][col]);
+        }
+    }
+    found
+}
+
+fn backtrack<T: Copy + Eq + Hash>(
+    board: &Vec<Vec<T>>,
+    status: &mut Vec<Vec<Option<Status>>>,
+    row: usize,
+    n: usize,
+    colors: &mut HashSet<T>,
+    fixed: &[Option<usize>],
 ) -> Option<Vec<usize>> {
     if row == n {
         // there is a `data-cell-idx` attribute in the board, which is the index of
@@ -28,9 +91,9 @@ fn backtrack(
         let mut result = Vec::with_capacity(n);
         // idx = i * m + j
         // I remember it like this, idx = i'm j
-        for row in 0..n {
-            for col in 0..n {
-                if status[row][col].is_some() {
+        for (row, cells) in status.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if cell.is_some() {
                     result.push(row * n + col);
                 }
             }
@@ -39,12 +102,16 @@ fn backtrack(
     }
 
     for col in 0..n {
+        // a queen the player already placed is the only option on its row
+        if fixed[row].is_some_and(|fixed_col| fixed_col != col) {
+            continue;
+        }
         if is_valid(board, status, row, col, n, colors) {
             // change state
             status[row][col] = Some(Status::Queen);
             colors.insert(board[row][col]);
             // backtrack
-            let backtrack_result = backtrack(board, status, row + 1, n, colors);
+            let backtrack_result = backtrack(board, status, row + 1, n, colors, fixed);
             if backtrack_result.is_some() {
                 return backtrack_result;
             }
@@ -56,13 +123,13 @@ fn backtrack(
     None
 }
 
-fn is_valid(
-    board: &Vec<Vec<CellColor>>,
-    status: &mut Vec<Vec<Option<Status>>>,
+fn is_valid<T: Copy + Eq + Hash>(
+    board: &[Vec<T>],
+    status: &[Vec<Option<Status>>],
     row: usize,
     col: usize,
     n: usize,
-    colors: &HashSet<CellColor>,
+    colors: &HashSet<T>,
 ) -> bool {
     // no need to check row because we put only one queen in each row
     // check col, before the row, col
@@ -71,10 +138,8 @@ fn is_valid(
     // every color grid must have a queen
 
     // check previous columns
-    for r in 0..row {
-        if status[r][col].is_some() {
-            return false;
-        }
+    if status[..row].iter().any(|cells| cells[col].is_some()) {
+        return false;
     }
 
     // check previous one distance diagonals
@@ -82,10 +147,8 @@ fn is_valid(
         (row.wrapping_sub(1), col.wrapping_sub(1)), // up-left
         (row.wrapping_sub(1), col + 1),             // up-right
     ] {
-        if (0..n).contains(&nr) && (0..n).contains(&nc) {
-            if status[nr][nc].is_some() {
-                return false;
-            }
+        if (0..n).contains(&nr) && (0..n).contains(&nc) && status[nr][nc].is_some() {
+            return false;
         }
     }
 
@@ -135,10 +198,12 @@ fn dfs_found_queen_in_same_color_grid(
         (i.wrapping_sub(1), j + 1),
         (i + 1, j.wrapping_sub(1)),
     ] {
-        if (0..board.len()).contains(&ni) && (0..board[0].len()).contains(&nj) && !visited[ni][nj] {
-            if dfs_found_queen_in_same_color_grid(board, status, cur_color, ni, nj, visited) {
-                return true;
-            }
+        if (0..board.len()).contains(&ni)
+            && (0..board[0].len()).contains(&nj)
+            && !visited[ni][nj]
+            && dfs_found_queen_in_same_color_grid(board, status, cur_color, ni, nj, visited)
+        {
+            return true;
         }
     }
     false
@@ -158,30 +223,60 @@ pub enum CellColor {
     Pink,
 }
 
+// color names as they show up in the cell aria-label of the english UI, all
+// lowercase. Other locales translate them, those pages are read from the
+// `cell-color-N` class instead.
+const ARIA_COLOR_NAMES: &[(&str, CellColor)] = &[
+    ("peach orange", CellColor::PeachOrange),
+    ("soft blue", CellColor::SoftBlue),
+    ("pastel green", CellColor::PastelGreen),
+    ("light gray", CellColor::LightGray),
+    ("vibrant coral", CellColor::VibrantCoral),
+    ("lime yellow", CellColor::LimeYellow),
+    ("lavender", CellColor::Lavender),
+    ("warm beige", CellColor::WarmBeige),
+    ("dark gray", CellColor::DarkGray),
+    ("pink", CellColor::Pink),
+];
+
 impl CellColor {
+    pub const ALL: [CellColor; 10] = [
+        CellColor::PeachOrange,
+        CellColor::SoftBlue,
+        CellColor::PastelGreen,
+        CellColor::LightGray,
+        CellColor::VibrantCoral,
+        CellColor::LimeYellow,
+        CellColor::Lavender,
+        CellColor::WarmBeige,
+        CellColor::DarkGray,
+        CellColor::Pink,
+    ];
+
     pub fn from_aria_label(label: &str) -> Option<Self> {
-        if label.contains("Peach Orange") {
-            Some(CellColor::PeachOrange)
-        } else if label.contains("Soft Blue") {
-            Some(CellColor::SoftBlue)
-        } else if label.contains("Pastel Green") {
-            Some(CellColor::PastelGreen)
-        } else if label.contains("Light Gray") {
-            Some(CellColor::LightGray)
-        } else if label.contains("Vibrant Coral") {
-            Some(CellColor::VibrantCoral)
-        } else if label.contains("Lime Yellow") {
-            Some(CellColor::LimeYellow)
-        } else if label.contains("Lavender") {
-            Some(CellColor::Lavender)
-        } else if label.contains("Warm Beige") {
-            Some(CellColor::WarmBeige)
-        } else if label.contains("Dark Gray") {
-            Some(CellColor::DarkGray)
-        } else if label.contains("Pink") {
-            Some(CellColor::Pink)
-        } else {
-            None
+        let label = label.to_lowercase();
+        ARIA_COLOR_NAMES
+            .iter()
+            .find(|(name, _)| label.contains(name))
+            .map(|(_, color)| *color)
+    }
+
+    // the cells also carry a `cell-color-N` class, which is the same in every
+    // locale, so prefer it over the aria-label when it's there. Every color
+    // has an index, only an index past them falls back to the aria-label.
+    pub fn from_class_index(idx: usize) -> Option<Self> {
+        match idx {
+            0 => Some(CellColor::Lavender),
+            1 => Some(CellColor::PeachOrange),
+            2 => Some(CellColor::SoftBlue),
+            3 => Some(CellColor::PastelGreen),
+            4 => Some(CellColor::LightGray),
+            5 => Some(CellColor::VibrantCoral),
+            6 => Some(CellColor::LimeYellow),
+            7 => Some(CellColor::WarmBeige),
+            8 => Some(CellColor::DarkGray),
+            9 => Some(CellColor::Pink),
+            _ => None,
         }
     }
 }
@@ -1,90 +1,399 @@
 use scraper::{Html, Selector};
+use std::error::Error;
+use std::fmt;
 
 use crate::game_logic::CellColor;
 
-pub fn parse_board(html_content: &str) -> Vec<Vec<CellColor>> {
+// Something about the scraped board that doesn't look like the markup we
+// expect, rows and columns are 1-based like on the page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardHtmlIssue {
+    MissingGrid,
+    MissingDimensions { style: String },
+    NotSquare { rows: usize, cols: usize },
+    CellCount { expected: usize, found: usize },
+    UnknownPosition { cell: usize, label: String },
+    PositionOutOfRange { cell: usize, row: usize, col: usize },
+    IndexOutOfRange { idx: usize, cells: usize },
+    DuplicateIndex { idx: usize },
+    UnknownColor { idx: usize, label: String },
+    MissingCell { row: usize, col: usize },
+}
+
+impl fmt::Display for BoardHtmlIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardHtmlIssue::MissingGrid => write!(f, "no #queens-grid element"),
+            BoardHtmlIssue::MissingDimensions { style } => {
+                write!(f, "no --rows/--cols in grid style '{}'", style)
+            }
+            BoardHtmlIssue::NotSquare { rows, cols } => {
+                write!(f, "grid is {}x{}, expected a square board", rows, cols)
+            }
+            BoardHtmlIssue::CellCount { expected, found } => {
+                write!(f, "found {} cells, expected {}", found, expected)
+            }
+            BoardHtmlIssue::UnknownPosition { cell, label } => write!(
+                f,
+                "cell #{} has no data-cell-idx and no position in its label '{}'",
+                cell + 1,
+                label
+            ),
+            BoardHtmlIssue::PositionOutOfRange { cell, row, col } => write!(
+                f,
+                "cell #{} is labelled row {}, column {}, outside the grid",
+                cell + 1,
+                row,
+                col
+            ),
+            BoardHtmlIssue::IndexOutOfRange { idx, cells } => {
+                write!(f, "data-cell-idx {} is out of range (0..{})", idx, cells)
+            }
+            BoardHtmlIssue::DuplicateIndex { idx } => {
+                write!(f, "data-cell-idx {} appears more than once", idx)
+            }
+            BoardHtmlIssue::UnknownColor { idx, label } => {
+                write!(f, "cell {} has an unknown color, label '{}'", idx, label)
+            }
+            BoardHtmlIssue::MissingCell { row, col } => {
+                write!(f, "no cell for row {}, column {}", row, col)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BoardHtmlReport {
+    pub rows: usize,
+    pub cols: usize,
+    pub cells_found: usize,
+    pub issues: Vec<BoardHtmlIssue>,
+}
+
+impl BoardHtmlReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for BoardHtmlReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(
+                f,
+                "Board HTML looks fine ({}x{}, {} cells)",
+                self.rows, self.cols, self.cells_found
+            );
+        }
+        write!(f, "Board HTML has {} problem(s):", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  - {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for BoardHtmlReport {}
+
+// Checks the structure of the board markup without solving anything, so a
+// LinkedIn DOM change shows up as a list of problems instead of a wrong board
+pub fn validate_board_html(html_content: &str) -> BoardHtmlReport {
+    let document = Html::parse_document(html_content);
+    scan_board(&document).0
+}
+
+pub fn parse_board(html_content: &str) -> Result<Vec<Vec<CellColor>>, BoardHtmlReport> {
     println!("Parsing HTML content of length: {}", html_content.len());
     let document = Html::parse_document(html_content);
 
+    let (report, cells) = scan_board(&document);
+    println!("{}", report);
+    if !report.is_ok() {
+        return Err(report);
+    }
+
+    // every cell was found exactly once, otherwise the report has an issue
+    let board: Vec<Vec<CellColor>> = cells
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|cell| cell.expect("cell checked by scan_board"))
+                .collect()
+        })
+        .collect();
+
+    println!("Final board:");
+    for row in &board {
+        println!("{:?}", row);
+    }
+
+    Ok(board)
+}
+
+// Walks the grid and its cells, collecting every structural problem instead of
+// stopping at the first one. Cells that couldn't be read stay `None`.
+fn scan_board(document: &Html) -> (BoardHtmlReport, Vec<Vec<Option<CellColor>>>) {
+    let mut report = BoardHtmlReport::default();
+
     // Get board dimensions from the grid style
     let grid_selector = Selector::parse("div#queens-grid").expect("Failed to parse grid selector");
-    let grid = document
-        .select(&grid_selector)
-        .next()
-        .expect("Could not find queens-grid element");
-
-    let style = grid
-        .value()
-        .attr("style")
-        .expect("Grid element has no style attribute");
-    println!("Found grid style: {}", style);
+    let Some(grid) = document.select(&grid_selector).next() else {
+        report.issues.push(BoardHtmlIssue::MissingGrid);
+        return (report, Vec::new());
+    };
 
-    let rows = style
-        .split("--rows: ")
-        .nth(1)
-        .expect("Could not find --rows in style")
-        .split(";")
-        .next()
-        .expect("Could not parse rows value")
-        .trim()
-        .parse::<usize>()
-        .expect("Could not parse rows as number");
-
-    let cols = style
-        .split("--cols: ")
-        .nth(1)
-        .expect("Could not find --cols in style")
-        .split(";")
-        .next()
-        .expect("Could not parse cols value")
-        .trim()
-        .parse::<usize>()
-        .expect("Could not parse cols as number");
+    let style = grid.value().attr("style").unwrap_or_default();
+    println!("Found grid style: {}", style);
+    let (Some(rows), Some(cols)) = (
+        style_dimension(style, "--rows"),
+        style_dimension(style, "--cols"),
+    ) else {
+        report.issues.push(BoardHtmlIssue::MissingDimensions {
+            style: style.to_string(),
+        });
+        return (report, Vec::new());
+    };
+    report.rows = rows;
+    report.cols = cols;
 
     // it's always a n*n board, so rows == cols
     println!("Board dimensions: {}x{}", rows, cols);
+    if rows != cols {
+        report.issues.push(BoardHtmlIssue::NotSquare { rows, cols });
+    }
 
     let cell_selector =
         Selector::parse("div.queens-cell-with-border").expect("Failed to parse cell selector");
-    let mut board = vec![vec![CellColor::Lavender; cols]; rows];
-    let mut cells_found = 0;
+    // no default color, a cell that never shows up stays None and gets reported
+    let mut board: Vec<Vec<Option<CellColor>>> = vec![vec![None; cols]; rows];
+    let mut seen = vec![false; rows * cols];
+
+    for (i, cell) in grid.select(&cell_selector).enumerate() {
+        report.cells_found += 1;
+        let label = cell.value().attr("aria-label").unwrap_or_default();
 
-    for cell in document.select(&cell_selector) {
-        let label = cell
+        // `data-cell-idx` is the flattened index (idx = row * cols + col) and
+        // doesn't depend on the UI language, unlike the aria-label
+        let idx = match cell
             .value()
-            .attr("aria-label")
-            .expect("Cell has no aria-label");
-        let row = label
-            .split("row ")
-            .nth(1)
-            .and_then(|s| s.split(",").next())
-            .and_then(|s| s.parse::<usize>().ok())
-            .expect("Could not parse row number")
-            - 1; // Convert to 0-based index
-
-        let col = label
-            .split("column ")
-            .nth(1)
-            .and_then(|s| s.parse::<usize>().ok())
-            .expect("Could not parse column number")
-            - 1; // Convert to 0-based index
+            .attr("data-cell-idx")
+            .and_then(|idx| idx.trim().parse::<usize>().ok())
+        {
+            Some(idx) => idx,
+            None => match position_from_aria_label(label) {
+                // a column past the end would silently wrap onto the next row
+                Some((row, col)) if row < rows && col < cols => row * cols + col,
+                Some((row, col)) => {
+                    report.issues.push(BoardHtmlIssue::PositionOutOfRange {
+                        cell: i,
+                        row: row + 1,
+                        col: col + 1,
+                    });
+                    continue;
+                }
+                None => {
+                    report.issues.push(BoardHtmlIssue::UnknownPosition {
+                        cell: i,
+                        label: label.to_string(),
+                    });
+                    continue;
+                }
+            },
+        };
+        if idx >= rows * cols {
+            report.issues.push(BoardHtmlIssue::IndexOutOfRange {
+                idx,
+                cells: rows * cols,
+            });
+            continue;
+        }
+        if seen[idx] {
+            report.issues.push(BoardHtmlIssue::DuplicateIndex { idx });
+            continue;
+        }
+        seen[idx] = true;
 
+        let (row, col) = (idx / cols, idx % cols);
         println!("Found cell: label='{}', row={}, col={}", label, row, col);
 
-        let cell_color = CellColor::from_aria_label(label).expect("Could not parse cell color");
-        board[row][col] = cell_color;
-        cells_found += 1;
+        let cell_color = cell
+            .value()
+            .classes()
+            .find_map(|class| class.strip_prefix("cell-color-"))
+            .and_then(|idx| idx.parse::<usize>().ok())
+            .and_then(CellColor::from_class_index)
+            .or_else(|| CellColor::from_aria_label(label));
+        match cell_color {
+            Some(color) => board[row][col] = Some(color),
+            None => report.issues.push(BoardHtmlIssue::UnknownColor {
+                idx,
+                label: label.to_string(),
+            }),
+        }
     }
 
     println!(
         "Found {} cells out of {} total cells",
-        cells_found,
+        report.cells_found,
         rows * cols
     );
-    println!("Final board:");
-    for row in &board {
-        println!("{:?}", row);
+    if report.cells_found != rows * cols {
+        report.issues.push(BoardHtmlIssue::CellCount {
+            expected: rows * cols,
+            found: report.cells_found,
+        });
+    }
+    for (idx, &found) in seen.iter().enumerate() {
+        if !found {
+            report.issues.push(BoardHtmlIssue::MissingCell {
+                row: idx / cols + 1,
+                col: idx % cols + 1,
+            });
+        }
+    }
+
+    (report, board)
+}
+
+// reads `n` out of a `--rows: n; --cols: n` grid style, all the LinkedIn game
+// grids are laid out like that
+pub(crate) fn style_dimension(style: &str, name: &str) -> Option<usize> {
+    style
+        .split(name)
+        .nth(1)?
+        .trim_start()
+        .trim_start_matches(':')
+        .split(';')
+        .next()?
+        .trim()
+        .parse::<usize>()
+        .ok()
+}
+
+// fallback for cells without a `data-cell-idx`, the label is something like
+// "Empty cell of color Peach Orange, row 1, column 2" in english, but the words
+// change with the locale, so just take the last two numbers in it (row, column)
+fn position_from_aria_label(label: &str) -> Option<(usize, usize)> {
+    let mut numbers = Vec::new();
+    let mut current: Option<usize> = None;
+    for c in label.chars() {
+        match c.to_digit(10) {
+            Some(d) => current = Some(current.unwrap_or(0) * 10 + d as usize),
+            None => {
+                if let Some(n) = current.take() {
+                    numbers.push(n);
+                }
+            }
+        }
+    }
+    if let Some(n) = current {
+        numbers.push(n);
+    }
+
+    let col = numbers.pop()?;
+    let row = numbers.pop()?;
+    // Convert to 0-based index
+    Some((row.checked_sub(1)?, col.checked_sub(1)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD1: &str = include_str!("../html_board1.html");
+    const BOARD2: &str = include_str!("../html_board2.html");
+
+    // every "of color <name>," in the labels replaced with `replacement`
+    fn replace_color_names(html: &str, replacement: &str) -> String {
+        let mut result = String::new();
+        let mut rest = html;
+        while let Some(start) = rest.find("of color ") {
+            let end = start + rest[start..].find(',').unwrap();
+            result += &rest[..start];
+            result += replacement;
+            rest = &rest[end..];
+        }
+        result + rest
+    }
+
+    #[test]
+    fn parses_the_saved_boards() {
+        for html in [BOARD1, BOARD2] {
+            let board = parse_board(html).unwrap();
+            assert_eq!(board.len(), board[0].len());
+            assert!(crate::queens(&board).is_ok());
+        }
+    }
+
+    #[test]
+    fn colors_come_from_the_class_whatever_the_label_says() {
+        for html in [BOARD1, BOARD2] {
+            let unlabelled = replace_color_names(html, "de couleur inconnue");
+            assert_eq!(
+                parse_board(&unlabelled).unwrap(),
+                parse_board(html).unwrap()
+            );
+        }
     }
 
-    board
+    #[test]
+    fn last_two_classes_are_read_on_a_localized_page() {
+        // the lavender and peach orange regions drawn as dark gray and pink,
+        // with labels no English name matches
+        let html = BOARD1
+            .replace("cell-color-0", "cell-color-8")
+            .replace("cell-color-1", "cell-color-9");
+        let localized = replace_color_names(&html, "de couleur gris foncé");
+        let expected: Vec<Vec<CellColor>> = parse_board(BOARD1)
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|color| match color {
+                        CellColor::Lavender => CellColor::DarkGray,
+                        CellColor::PeachOrange => CellColor::Pink,
+                        color => color,
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(parse_board(&localized).unwrap(), expected);
+    }
+
+    #[test]
+    fn positions_come_from_the_cell_index_whatever_the_label_says() {
+        let scrambled = BOARD1.replace("row 1,", "row 7,");
+        assert_eq!(
+            parse_board(&scrambled).unwrap(),
+            parse_board(BOARD1).unwrap()
+        );
+    }
+
+    #[test]
+    fn unmapped_class_falls_back_to_the_english_label() {
+        let html = BOARD1.replacen("cell-color-0", "cell-color-10", 1);
+        let board = parse_board(&html).unwrap();
+        let original = parse_board(BOARD1).unwrap();
+        // the only cell-color-0 cell is labelled Lavender
+        assert_eq!(board, original);
+    }
+
+    #[test]
+    fn label_positions_outside_the_grid_are_reported() {
+        // without the index, "row 1, column 8" on a 7x7 board would land on
+        // row 2, column 1
+        let unindexed = BOARD1.replace("data-cell-idx=", "data-old-idx=");
+        assert!(parse_board(&unindexed).is_ok());
+
+        let wrapped = unindexed.replace("row 1, column 7", "row 1, column 8");
+        let issues = match parse_board(&wrapped) {
+            Err(report) => report.issues,
+            Ok(_) => panic!("a cell outside the grid was accepted"),
+        };
+        assert!(issues.contains(&BoardHtmlIssue::PositionOutOfRange {
+            cell: 6,
+            row: 1,
+            col: 8
+        }));
+    }
 }
@@ -1,17 +1,33 @@
-use image::{DynamicImage, GenericImageView, Rgba};
+use image::{DynamicImage, GenericImageView, GrayImage, Rgb, RgbImage, Rgba};
+use rayon::prelude::*;
 use std::error::Error;
+use std::io::Read;
 
 use crate::CellColor;
+use crate::board_locator::{crop_bounds, crop_to_board, frame_luma, locate_board, luma_buffer};
+use crate::calibration::Calibration;
+use crate::cielab::{delta_e_2000, rgb_to_lab};
 
 // const COLOR_TOLERANCE: u8 = 20;
-// const BLACK_THRESHOLD: u8 = 30; // Increased threshold for black detection
 // const MIN_LINE_LENGTH: u32 = 10; // Minimum length of a line to be considered a grid line
 // const LINE_DETECTION_THRESHOLD: u32 = 2; // Reduced from 3 to 2
-const MIN_GRID_SIZE: u32 = 6; // Minimum expected grid size
-const WINDOW_SIZE: u32 = 5; // Size of sliding window for line detection
+const MIN_LINE_COVERAGE: f32 = 0.3; // A line has to cross at least this much of the image
+const SAMPLE_STEPS: u32 = 8; // Sample points per side of a cell
+const SAMPLE_AGREEMENT: f32 = 24.0; // A sample this close to the cell's color agrees with it
+const LOW_CONFIDENCE: f32 = 0.5; // Cells below this should be checked by a human
+const MARK_DISTANCE: f32 = 60.0; // A pixel this far from the cell color is part of an icon
+const MIN_CROSS_COVERAGE: f32 = 0.02; // Less of the middle than this is an empty cell
+const MIN_QUEEN_COVERAGE: f32 = 0.2; // The X covers ~5-10% of the middle, the crown ~40%
+const MAX_DELTA_E: f32 = 20.0; // A color further than this from every palette entry isn't one of them
+const AMBIGUOUS_DELTA_E: f32 = 3.0; // The two closest palette colors within this of each other is a toss-up
+const MAX_GRID_SIZE: usize = 15; // Largest board the flat cell fallback looks for
+const MIN_FLAT_CELLS: f32 = 0.9; // Share of the cells (and their samples) that have to be one color
+const PAGE_DISTANCE: f32 = 12.0; // A pixel this close to the page color is page, Warm Beige is ~23 off
+const DARK_BACKGROUND_LUMA: u8 = 100; // A page darker than this is the dark theme
 
-// Add color constants at module level
-const COLOR_DEFINITIONS: &[((u8, u8, u8), CellColor)] = &[
+// Add color constants at module level. These are the light theme's, nobody
+// measured the dark theme's cells yet, see `Theme`
+pub(crate) const COLOR_DEFINITIONS: &[((u8, u8, u8), CellColor)] = &[
     ((241, 203, 154), CellColor::PeachOrange),
     ((229, 130, 104), CellColor::VibrantCoral),
     ((164, 190, 249), CellColor::SoftBlue),
@@ -24,25 +40,161 @@ const COLOR_DEFINITIONS: &[((u8, u8, u8), CellColor)] = &[
     ((241, 234, 218), CellColor::WarmBeige),
 ];
 
+// Only changes how the page and lines are told apart. The cells of a dark
+// screenshot are still named after the closest light palette color: every
+// region gets a different one (see `label_clusters`), so the regions and the
+// solution are right, but the names can be off until a calibration profile
+// made from a dark screenshot supplies the real colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
 #[derive(Debug)]
 pub struct BoardImage {
     image: DynamicImage,
+    // `image` converted once, what the cells are sampled from
+    pixels: RgbImage,
+    // the average cell, only for sizing things drawn on it
     cell_width: u32,
     cell_height: u32,
     grid_width: u32,
     grid_height: u32,
+    horizontal_lines: Vec<u32>,
+    vertical_lines: Vec<u32>,
+    // inside of every cell, row by row, see `measure_cells`
+    cells: Vec<Vec<CellRect>>,
+    // where `image` starts in the screenshot it was cropped from
+    origin: (u32, u32),
+    theme: Theme,
+    // the widest line found, in pixels, see `calibration`
+    line_thickness: u32,
+    calibration: Calibration,
+}
+
+// The inside of a cell, from the inner edge of the line on one side to the
+// inner edge of the line on the other (all four are cell pixels, `right` and
+// `bottom` the last ones before the next line)
+#[derive(Debug, Clone, Copy)]
+struct CellRect {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+}
+
+impl CellRect {
+    // the point `fx`, `fy` of the way across (0 to 1)
+    fn at(&self, fx: f32, fy: f32) -> (u32, u32) {
+        (
+            self.left + (fx * (self.right - self.left) as f32) as u32,
+            self.top + (fy * (self.bottom - self.top) as f32) as u32,
+        )
+    }
+}
+
+// The color read for one cell, `region` is the cluster it ended up in and
+// `confidence` goes from 0 (a guess) to 1 (all samples agree and the color is
+// clearly closer to its own region than to any other). `runner_up` is the
+// next closest palette color to the region, `ambiguous` is set when the region
+// is about as close to that one as to its own (see `AMBIGUOUS_DELTA_E`).
+#[derive(Debug, Clone, Copy)]
+pub struct CellReading {
+    pub color: CellColor,
+    pub region: usize,
+    pub confidence: f32,
+    pub runner_up: Option<CellColor>,
+    pub ambiguous: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BoardReading {
+    pub cells: Vec<Vec<CellReading>>,
+    // (row, col) of the cells below `LOW_CONFIDENCE`, the ones worth asking
+    // someone to confirm
+    pub low_confidence: Vec<(usize, usize)>,
+}
+
+// What the player put on a cell, read from a screenshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellMark {
+    Empty,
+    Cross,
+    Queen,
+}
+
+#[derive(Debug, Clone)]
+pub struct BoardState {
+    pub colors: Vec<Vec<CellColor>>,
+    pub marks: Vec<Vec<CellMark>>,
+}
+
+impl BoardState {
+    // (row, col) of the queens already on the board
+    pub fn queens(&self) -> Vec<(usize, usize)> {
+        let mut queens = Vec::new();
+        for (row, marks) in self.marks.iter().enumerate() {
+            for (col, mark) in marks.iter().enumerate() {
+                if *mark == CellMark::Queen {
+                    queens.push((row, col));
+                }
+            }
+        }
+        queens
+    }
 }
 
 impl BoardImage {
+    // Reads with the compiled in defaults, `new_with_calibration` for a profile
     pub fn new(image: DynamicImage) -> Result<Self, Box<dyn Error>> {
+        Self::new_with_calibration(image, Calibration::default())
+    }
+
+    pub fn new_with_calibration(
+        image: DynamicImage,
+        calibration: Calibration,
+    ) -> Result<Self, Box<dyn Error>> {
         let (width, height) = image.dimensions();
         println!("Image dimensions: {}x{}", width, height);
+        if width == 0 || height == 0 {
+            return Err("The image is empty".into());
+        }
+
+        // everything below reads these buffers instead of going through
+        // `DynamicImage` pixel by pixel
+        let luma = luma_buffer(&image);
+        let pixels = image.to_rgb8();
+
+        let theme = luma_theme(&luma);
+        println!("Detected {:?} theme", theme);
+        if theme == Theme::Dark && calibration.palette.is_empty() {
+            println!(
+                "Warning: no measured colors for the dark theme, the regions are right but their color names may not be, calibrate with a dark screenshot to fix them"
+            );
+        }
+        // on a dark page whatever is past the image edge is more of the page,
+        // not something lighter than the lines
+        let page_luma = frame_luma(&luma).filter(|_| theme == Theme::Dark);
 
         // Detect lines using the consolidated function
-        let horizontal_lines = detect_lines(&image, true);
-        dbg!(&horizontal_lines);
-        let vertical_lines = detect_lines(&image, false);
-        dbg!(&vertical_lines);
+        let (horizontal_runs, vertical_runs) = rayon::join(
+            || detect_lines(&luma, true, page_luma, &calibration),
+            || detect_lines(&luma, false, page_luma, &calibration),
+        );
+        let line_thickness = horizontal_runs
+            .iter()
+            .chain(&vertical_runs)
+            .map(|(start, end)| end - start)
+            .max()
+            .unwrap_or(0);
+        let centers = |runs: Vec<(u32, u32)>| -> Vec<u32> {
+            runs.into_iter()
+                .map(|(start, end)| start + (end - start) / 2)
+                .collect()
+        };
+        let mut horizontal_lines = centers(horizontal_runs);
+        let mut vertical_lines = centers(vertical_runs);
 
         println!(
             "Found {} horizontal lines and {} vertical lines",
@@ -55,6 +207,8 @@ impl BoardImage {
         // vertical_lines = remove_close_lines(&vertical_lines, width / 20);
         // horizontal_lines = remove_close_lines(&horizontal_lines, height / 10);
         // vertical_lines = remove_close_lines(&vertical_lines, width / 10);
+        trim_stray_lines(&mut horizontal_lines);
+        trim_stray_lines(&mut vertical_lines);
 
         println!(
             "After filtering: {} horizontal lines and {} vertical lines",
@@ -62,8 +216,23 @@ impl BoardImage {
             vertical_lines.len()
         );
 
-        if horizontal_lines.len() < MIN_GRID_SIZE as usize
-            || vertical_lines.len() < MIN_GRID_SIZE as usize
+        // lines too faint (or blurred away) to find one by one, the cells are
+        // still flat colors
+        if (horizontal_lines.len() < calibration.min_grid_size as usize
+            || vertical_lines.len() < calibration.min_grid_size as usize)
+            && let Some((horizontal, vertical)) = infer_grid_lines(&pixels, &calibration)
+        {
+            println!(
+                "Inferred a {}x{} grid from the flat cells of the image",
+                vertical.len() - 1,
+                horizontal.len() - 1
+            );
+            horizontal_lines = horizontal;
+            vertical_lines = vertical;
+        }
+
+        if horizontal_lines.len() < calibration.min_grid_size as usize
+            || vertical_lines.len() < calibration.min_grid_size as usize
         {
             return Err(format!(
                 "Could not detect enough grid lines. Found {} horizontal and {} vertical lines",
@@ -98,96 +267,573 @@ impl BoardImage {
             .into());
         }
 
+        let cells = measure_cells(&luma, &horizontal_lines, &vertical_lines);
+
         Ok(Self {
             image,
+            pixels,
             cell_width,
             cell_height,
             grid_width: grid_width as u32,
             grid_height: grid_height as u32,
+            horizontal_lines,
+            vertical_lines,
+            cells,
+            origin: (0, 0),
+            theme,
+            line_thickness,
+            calibration,
+        })
+    }
+
+    // The same board in another screenshot of the same size taken from the
+    // same place, like the next frame of a screen recording. Keeps the lines
+    // found on this one instead of looking for them again, None when the
+    // screenshot is too small to have the board where this one has it.
+    pub fn same_board_in(&self, screenshot: &DynamicImage) -> Option<Self> {
+        let (width, height) = self.image.dimensions();
+        let (x, y) = self.origin;
+        if screenshot.width() < x + width || screenshot.height() < y + height {
+            return None;
+        }
+        let image = screenshot.crop_imm(x, y, width, height);
+        Some(Self {
+            pixels: image.to_rgb8(),
+            image,
+            horizontal_lines: self.horizontal_lines.clone(),
+            vertical_lines: self.vertical_lines.clone(),
+            cells: self.cells.clone(),
+            calibration: self.calibration.clone(),
+            ..*self
+        })
+    }
+
+    // Same as `new`, but the image may be a whole screenshot with the board
+    // somewhere in it. Images that are already cropped to the board still
+    // work, the board is then (nearly) the whole image.
+    pub fn from_screenshot(image: DynamicImage) -> Result<Self, Box<dyn Error>> {
+        Self::from_screenshot_with_calibration(image, Calibration::default())
+    }
+
+    pub fn from_screenshot_with_calibration(
+        image: DynamicImage,
+        calibration: Calibration,
+    ) -> Result<Self, Box<dyn Error>> {
+        match locate_board(&image) {
+            Some(region) => {
+                println!(
+                    "Found board at ({}, {}), {}x{}",
+                    region.x, region.y, region.width, region.height
+                );
+                let bounds = crop_bounds(&image, region);
+                let mut board =
+                    Self::new_with_calibration(crop_to_board(&image, region), calibration)?;
+                board.origin = (bounds.x, bounds.y);
+                Ok(board)
+            }
+            None => {
+                println!("Could not locate the board, using the whole image");
+                Self::new_with_calibration(image, calibration)
+            }
+        }
+    }
+
+    // Names each region with the closest entry of the theme's palette. The cells
+    // are grouped by `get_color_regions` first and only the groups get a name,
+    // so a color that isn't in the palette (or renders a bit differently)
+    // still ends up as its own region instead of failing the whole board.
+    pub fn get_board_colors(&self) -> Result<Vec<Vec<CellColor>>, String> {
+        Ok(self
+            .read_board()?
+            .cells
+            .into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.color).collect())
+            .collect())
+    }
+
+    // Groups the cells into one cluster per region (n regions for an n*n
+    // board) by their sampled color, no palette involved. Returns a region id
+    // per cell.
+    pub fn get_color_regions(&self) -> Vec<Vec<usize>> {
+        let (colors, _) = self.sample_cells();
+        let (assignments, _) = cluster_colors(&colors, self.grid_height as usize);
+        assignments
+            .chunks(self.grid_width as usize)
+            .map(|row| row.to_vec())
+            .collect()
+    }
+
+    // median color of every cell in row-major order, with the share of its
+    // samples that agree with that median
+    fn sample_cells(&self) -> (Vec<[f32; 3]>, Vec<f32>) {
+        let cols = self.grid_width as usize;
+        let cells = self.grid_height as usize * cols;
+        // every cell on its own, in parallel
+        (0..cells)
+            .into_par_iter()
+            .map(|idx| {
+                let samples = self.sample_cell_colors((idx / cols) as u32, (idx % cols) as u32);
+                let color = median_color(&samples);
+                let agreeing = samples
+                    .iter()
+                    .filter(|pixel| {
+                        rgb_distance(color, [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32])
+                            <= SAMPLE_AGREEMENT
+                    })
+                    .count();
+                (color, agreeing as f32 / samples.len().max(1) as f32)
+            })
+            .unzip()
+    }
+
+    // Reads every cell with a confidence score, see `CellReading`. Fails when
+    // there are more regions than palette colors to name them, the region ids
+    // of `get_color_regions` still work for those boards.
+    pub fn read_board(&self) -> Result<BoardReading, String> {
+        let (rows, cols) = (self.grid_height as usize, self.grid_width as usize);
+        let (colors, agreement) = self.sample_cells();
+        let (assignments, centroids) = cluster_colors(&colors, rows);
+        let labels = label_clusters(&centroids, &self.palette())?;
+
+        let mut cells = Vec::with_capacity(rows);
+        let mut low_confidence = Vec::new();
+        for row in 0..rows {
+            let mut readings = Vec::with_capacity(cols);
+            for col in 0..cols {
+                let idx = row * cols + col;
+                let region = assignments[idx];
+                // halfway between two regions is 0, right on its own region is 1
+                let own = rgb_distance(colors[idx], centroids[region]);
+                let other = centroids
+                    .iter()
+                    .enumerate()
+                    .filter(|&(id, _)| id != region)
+                    .map(|(_, &centroid)| rgb_distance(colors[idx], centroid))
+                    .fold(f32::MAX, f32::min);
+                let separation = if other == f32::MAX {
+                    1.0
+                } else {
+                    ((other - own) / (other + own).max(f32::EPSILON)).clamp(0.0, 1.0)
+                };
+
+                let confidence = agreement[idx] * separation;
+                if confidence < LOW_CONFIDENCE {
+                    low_confidence.push((row, col));
+                }
+                let label = labels[region];
+                readings.push(CellReading {
+                    color: label.color,
+                    region,
+                    confidence,
+                    runner_up: label.runner_up,
+                    ambiguous: label.ambiguous,
+                });
+            }
+            cells.push(readings);
+        }
+
+        if !low_confidence.is_empty() {
+            println!("Low confidence cells (row, col): {:?}", low_confidence);
+        }
+        Ok(BoardReading {
+            cells,
+            low_confidence,
         })
     }
 
-    pub fn get_board_colors(&self) -> Vec<Vec<CellColor>> {
-        let mut board =
-            vec![vec![CellColor::LightGray; self.grid_width as usize]; self.grid_height as usize];
+    // Reads the regions from the borders instead of the fill colors. LinkedIn
+    // draws a thicker line between two different regions, so cells joined by a
+    // thin line belong together. Only looks at the luminance, so it works on
+    // grayscale or color-filtered screenshots too. Returns a region id per cell.
+    pub fn get_board_regions(&self) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+        let luma = luma_buffer(&self.image);
+        let contrast = line_contrast(&luma);
+        let (rows, cols) = (self.grid_height as usize, self.grid_width as usize);
+
+        // how dark the lines themselves are, the darker end of what's under
+        // the detected line centers (the thin lines may be blurred lighter)
+        let (left, right_edge) = (self.vertical_lines[0], self.vertical_lines[cols]);
+        let mut line_pixels: Vec<u8> = self
+            .horizontal_lines
+            .iter()
+            .flat_map(|&y| (left..=right_edge).map(move |x| (x, y)))
+            .map(|(x, y)| luma.get_pixel(x, y)[0])
+            .collect();
+        line_pixels.sort_unstable();
+        let line_luma = line_pixels[line_pixels.len() / 10] as f32;
 
+        // thickness of the border right of / below each cell, the outer border
+        // isn't measured. The background is sampled a fifth of a cell away from
+        // the line, clear of the crowns and X marks in the middle.
+        let mut right = vec![vec![0.0f32; cols]; rows];
+        let mut down = vec![vec![0.0f32; cols]; rows];
+        let mut thicknesses = Vec::with_capacity(2 * rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let (top, bottom) = (self.horizontal_lines[row], self.horizontal_lines[row + 1]);
+                let (left, right_x) = (self.vertical_lines[col], self.vertical_lines[col + 1]);
+                if col + 1 < cols {
+                    let reach = (right_x - left) / 5;
+                    right[row][col] = border_thickness(
+                        &luma,
+                        right_x,
+                        (top, bottom),
+                        false,
+                        reach,
+                        contrast,
+                        line_luma,
+                    );
+                    thicknesses.push(right[row][col]);
+                }
+                if row + 1 < rows {
+                    let reach = (bottom - top) / 5;
+                    down[row][col] = border_thickness(
+                        &luma,
+                        bottom,
+                        (left, right_x),
+                        true,
+                        reach,
+                        contrast,
+                        line_luma,
+                    );
+                    thicknesses.push(down[row][col]);
+                }
+            }
+        }
+        thicknesses.sort_by(|a, b| a.total_cmp(b));
+        let split = thick_border_split(&thicknesses)
+            .ok_or("Could not tell thick and thin borders apart")?;
+        println!("Borders thicker than {:.2}px separate regions", split);
+
+        // flood fill across the thin borders
+        let mut regions = vec![vec![usize::MAX; cols]; rows];
+        let mut region_count = 0;
+        for start_row in 0..rows {
+            for start_col in 0..cols {
+                if regions[start_row][start_col] != usize::MAX {
+                    continue;
+                }
+                let mut stack = vec![(start_row, start_col)];
+                regions[start_row][start_col] = region_count;
+                while let Some((row, col)) = stack.pop() {
+                    let mut neighbours = Vec::with_capacity(4);
+                    if col + 1 < cols && right[row][col] <= split {
+                        neighbours.push((row, col + 1));
+                    }
+                    if col > 0 && right[row][col - 1] <= split {
+                        neighbours.push((row, col - 1));
+                    }
+                    if row + 1 < rows && down[row][col] <= split {
+                        neighbours.push((row + 1, col));
+                    }
+                    if row > 0 && down[row - 1][col] <= split {
+                        neighbours.push((row - 1, col));
+                    }
+                    for (r, c) in neighbours {
+                        if regions[r][c] == usize::MAX {
+                            regions[r][c] = region_count;
+                            stack.push((r, c));
+                        }
+                    }
+                }
+                region_count += 1;
+            }
+        }
+
+        println!("Found {} regions from the borders", region_count);
+        for row in &regions {
+            println!("{:?}", row);
+        }
+        // a queens board has exactly one region per row
+        if region_count != rows {
+            return Err(format!(
+                "Found {} regions from the borders, expected {}",
+                region_count, rows
+            )
+            .into());
+        }
+
+        Ok(regions)
+    }
+
+    // Samples a ring of points inside the cell, staying away from the borders
+    // (anti-aliasing, thick region lines) and from the middle, where the crown
+    // or the X sits
+    fn sample_cell_colors(&self, row: u32, col: u32) -> Vec<Rgb<u8>> {
+        self.sample_points(row, col)
+            .into_iter()
+            .map(|(x, y)| sample_pixel(&self.pixels, x, y))
+            .collect()
+    }
+
+    // where `sample_cell_colors` looks, in image coordinates
+    fn sample_points(&self, row: u32, col: u32) -> Vec<(u32, u32)> {
+        let mut points = Vec::new();
+        let cell = self.cells[row as usize][col as usize];
+        let inset = self.calibration.sample_inset;
+        let center = self.calibration.sample_center;
+
+        for y_step in 0..SAMPLE_STEPS {
+            for x_step in 0..SAMPLE_STEPS {
+                // from 15% to 85% of the cell with the default inset
+                let fx = inset + (1.0 - 2.0 * inset) * x_step as f32 / (SAMPLE_STEPS - 1) as f32;
+                let fy = inset + (1.0 - 2.0 * inset) * y_step as f32 / (SAMPLE_STEPS - 1) as f32;
+                if (fx - 0.5).abs() < center && (fy - 0.5).abs() < center {
+                    continue;
+                }
+                points.push(cell.at(fx, fy));
+            }
+        }
+
+        points
+    }
+
+    // Draws what the analysis saw on top of the (cropped) board: the detected
+    // lines in magenta, every sample point as a small black and white dot and
+    // a swatch of the palette color each cell was classified as in its middle.
+    // A swatch that doesn't match the cell around it is a misread color, low
+    // confidence cells get a red frame around the swatch.
+    pub fn write_debug_overlay(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut overlay = self.image.to_rgba8();
+        let (width, height) = overlay.dimensions();
+        let line_color = Rgba([255, 0, 255, 255]);
+
+        for &y in &self.horizontal_lines {
+            for x in 0..width {
+                overlay.put_pixel(x, y.min(height - 1), line_color);
+            }
+        }
+        for &x in &self.vertical_lines {
+            for y in 0..height {
+                overlay.put_pixel(x.min(width - 1), y, line_color);
+            }
+        }
+
+        let reading = self.read_board()?;
+        let palette = self.palette();
+        let swatch = (self.cell_width.min(self.cell_height) / 5).max(3);
         for row in 0..self.grid_height {
             for col in 0..self.grid_width {
-                let cell_colors = self.sample_cell_colors(row, col);
-                // difference of colors is about 14 diff at R
-                board[row as usize][col as usize] = detect_dominant_color(&cell_colors);
+                for (x, y) in self.sample_points(row, col) {
+                    fill_rect(
+                        &mut overlay,
+                        x.saturating_sub(1),
+                        y.saturating_sub(1),
+                        3,
+                        Rgba([0, 0, 0, 255]),
+                    );
+                    fill_rect(&mut overlay, x, y, 1, Rgba([255, 255, 255, 255]));
+                }
+
+                let cell = reading.cells[row as usize][col as usize];
+                let (r, g, b) = palette
+                    .iter()
+                    .find(|(_, color)| *color == cell.color)
+                    .map(|(rgb, _)| *rgb)
+                    .unwrap_or((0, 0, 0));
+                let (x, y) = self.cells[row as usize][col as usize].at(0.5, 0.5);
+                let (x, y) = (x.saturating_sub(swatch / 2), y.saturating_sub(swatch / 2));
+                let frame = if cell.confidence < LOW_CONFIDENCE {
+                    Rgba([255, 0, 0, 255])
+                } else {
+                    Rgba([0, 0, 0, 255])
+                };
+                fill_rect(
+                    &mut overlay,
+                    x.saturating_sub(2),
+                    y.saturating_sub(2),
+                    swatch + 4,
+                    frame,
+                );
+                fill_rect(&mut overlay, x, y, swatch, Rgba([r, g, b, 255]));
             }
         }
 
-        board
-    }
-
-    fn sample_cell_colors(&self, row: u32, col: u32) -> Vec<Rgba<u8>> {
-        let mut colors = Vec::new();
-        // the +1 is a small offset to avoid sampling the border of the cell
-        // to ensure we are sampling inside the cell
-        let cell_x = 1 + col * (self.cell_width + 1);
-        let cell_y = 1 + row * (self.cell_height + 1);
-
-        // let x_steps = 5;
-        // let y_steps = 5;
-        // let x_step = self.cell_width / x_steps;
-        // let y_step = self.cell_height / y_steps;
-
-        // // Sample grid points
-        // for y_offset in 1..y_steps {
-        //     for x_offset in 1..x_steps {
-        //         let x = cell_x + x_offset * x_step;
-        //         let y = cell_y + y_offset * y_step;
-        //         if let Some(pixel) = sample_pixel(&self.image, x, y) {
-        //             colors.push(pixel);
-        //         }
-        //     }
-        // }
-
-        // // Sample corners if needed
-        // if colors.len() < 4 {
-        //     let corners = [
-        //         (cell_x + x_step, cell_y + y_step),
-        //         (cell_x + self.cell_width - x_step, cell_y + y_step),
-        //         (cell_x + x_step, cell_y + self.cell_height - y_step),
-        //         (
-        //             cell_x + self.cell_width - x_step,
-        //             cell_y + self.cell_height - y_step,
-        //         ),
-        //     ];
-
-        //     for (x, y) in corners {
-        //         if let Some(pixel) = sample_pixel(&self.image, x, y) {
-        //             colors.push(pixel);
-        //         }
-        //     }
-        // }
-
-        // dbg!("not enough color samples");
-
-        // Sample center if still empty
-        // if colors.is_empty() {
-        // center of the bottom left corner of the cell
-        // if let Some(pixel) = sample_pixel(&self.image, center_x, center_y) {
-        //     colors.push(pixel);
-        // }
-        let center_x = cell_x + self.cell_width / 4; // 1/4 from left
-        let center_y = cell_y + (self.cell_height * 3) / 4; // 1/4 from bottom
-        colors.push(sample_pixel(&self.image, center_x, center_y));
-        // }
-
-        colors
-    }
-}
-
-// if the image is low quality, the pixels are not black, but a dark gray
-// fn is_dark(pixel: Rgba<u8>) -> bool {
-//     pixel[0] < BLACK_THRESHOLD && pixel[1] < BLACK_THRESHOLD && pixel[2] < BLACK_THRESHOLD
-// }
+        overlay.save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+
+    // Looks at the middle of every cell for the crown or the X the player
+    // placed. Both are drawn in a color far from the cell's own, an X is a
+    // couple of thin strokes and the crown fills a big part of the middle, so
+    // how much of the middle differs from the cell color is enough to tell
+    // them apart, whatever the icon's color.
+    pub fn detect_marks(&self) -> Vec<Vec<CellMark>> {
+        self.cells
+            .par_iter()
+            .enumerate()
+            .map(|(row, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(|(col, cell)| {
+                        let background =
+                            median_color(&self.sample_cell_colors(row as u32, col as u32));
+                        let (left, top) = cell.at(0.3, 0.3);
+                        let (right, bottom) = cell.at(0.7, 0.7);
+                        let (mut ink, mut total) = (0, 0);
+                        // the middle 40% of the cell
+                        for y in top..bottom {
+                            for x in left..right {
+                                let pixel = sample_pixel(&self.pixels, x, y);
+                                total += 1;
+                                if rgb_distance(
+                                    background,
+                                    [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32],
+                                ) > MARK_DISTANCE
+                                {
+                                    ink += 1;
+                                }
+                            }
+                        }
+
+                        let coverage = ink as f32 / total.max(1) as f32;
+                        if coverage < MIN_CROSS_COVERAGE {
+                            CellMark::Empty
+                        } else if coverage < MIN_QUEEN_COVERAGE {
+                            CellMark::Cross
+                        } else {
+                            CellMark::Queen
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // The colors and whatever the player already placed on them
+    pub fn get_board_state(&self) -> Result<BoardState, String> {
+        Ok(BoardState {
+            colors: self.get_board_colors()?,
+            marks: self.detect_marks(),
+        })
+    }
+
+    // Middle of a cell between its own lines, in the coordinates of the
+    // screenshot the board came from (not the cropped board)
+    pub fn cell_center(&self, row: usize, col: usize) -> (u32, u32) {
+        let x = (self.vertical_lines[col] + self.vertical_lines[col + 1]) / 2;
+        let y = (self.horizontal_lines[row] + self.horizontal_lines[row + 1]) / 2;
+        (self.origin.0 + x, self.origin.1 + y)
+    }
 
-fn is_black(pixel: Rgba<u8>) -> bool {
-    pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 0
+    // the smaller side of a cell, in pixels
+    pub fn cell_size(&self) -> u32 {
+        self.cell_width.min(self.cell_height)
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme
+    }
+
+    pub fn grid_size(&self) -> usize {
+        self.grid_width as usize
+    }
+
+    // The built-in palette with the calibrated colors swapped in
+    pub fn palette(&self) -> Vec<((u8, u8, u8), CellColor)> {
+        COLOR_DEFINITIONS
+            .iter()
+            .map(|&(rgb, color)| {
+                let calibrated = self.calibration.palette.iter().find(|(_, c)| *c == color);
+                (calibrated.map_or(rgb, |(rgb, _)| *rgb), color)
+            })
+            .collect()
+    }
+
+    // A profile named `name` with what this board looks like: the colors of
+    // its regions as sampled, the thickest line and the settings it was read
+    // with. Save it with `Calibration::save_named`.
+    pub fn calibration(&self, name: &str) -> Result<Calibration, String> {
+        let reading = self.read_board()?;
+        let mut samples: Vec<(CellColor, Vec<Rgb<u8>>)> = Vec::new();
+        for row in 0..self.grid_height {
+            for col in 0..self.grid_width {
+                let color = reading.cells[row as usize][col as usize].color;
+                let cell_samples = self.sample_cell_colors(row, col);
+                match samples.iter_mut().find(|(c, _)| *c == color) {
+                    Some((_, all)) => all.extend(cell_samples),
+                    None => samples.push((color, cell_samples)),
+                }
+            }
+        }
+
+        let palette = samples
+            .into_iter()
+            .map(|(color, pixels)| {
+                let [r, g, b] = median_color(&pixels);
+                ((r as u8, g as u8, b as u8), color)
+            })
+            .collect();
+        Ok(Calibration {
+            name: name.to_string(),
+            palette,
+            line_thickness: self.line_thickness,
+            ..self.calibration.clone()
+        })
+    }
+}
+
+// Finds the inside of every cell between its own lines. How far a line
+// reaches into a cell is measured right there, so a thick region border
+// doesn't eat into the cells next to it and a thin line doesn't leave a
+// gap, however the borders of this board vary.
+fn measure_cells(luma: &GrayImage, horizontal: &[u32], vertical: &[u32]) -> Vec<Vec<CellRect>> {
+    let contrast = line_contrast(luma);
+    let mut cells = Vec::with_capacity(horizontal.len() - 1);
+    for rows in horizontal.windows(2) {
+        let mut row_cells = Vec::with_capacity(vertical.len() - 1);
+        for cols in vertical.windows(2) {
+            let (top, bottom) = (rows[0], rows[1]);
+            let (left, right) = (cols[0], cols[1]);
+            let extent = |line: u32, toward: i32, span: (u32, u32), is_horizontal: bool| {
+                line_extent(luma, line, toward, span, is_horizontal, contrast)
+            };
+            row_cells.push(CellRect {
+                left: left + extent(left, 1, (top, bottom), false),
+                top: top + extent(top, 1, (left, right), true),
+                right: right - extent(right, -1, (top, bottom), false),
+                bottom: bottom - extent(bottom, -1, (left, right), true),
+            });
+        }
+        cells.push(row_cells);
+    }
+    cells
+}
+
+// How many pixels from the center of `line` toward the cell (`toward` is 1 or
+// -1) are still part of the line, the median of three places along the
+// cell side between `span.0` and `span.1`. The cell color is taken a fifth of
+// the cell in, clear of the crowns and X marks, and the line never takes more
+// than that.
+fn line_extent(
+    luma: &GrayImage,
+    line: u32,
+    toward: i32,
+    span: (u32, u32),
+    is_horizontal: bool,
+    contrast: i32,
+) -> u32 {
+    let (width, height) = luma.dimensions();
+    let at = |p: i64, s: u32| {
+        let p = p.clamp(0, if is_horizontal { height } else { width } as i64 - 1) as u32;
+        let (x, y) = if is_horizontal { (s, p) } else { (p, s) };
+        luma.get_pixel(x.min(width - 1), y)[0] as i32
+    };
+    let limit = ((span.1 - span.0) / 5).max(1);
+
+    let mut extents: Vec<u32> = [3, 5, 7]
+        .iter()
+        .map(|tenths| {
+            let s = span.0 + (span.1 - span.0) * tenths / 10;
+            let cell = at(line as i64 + toward as i64 * limit as i64, s);
+            (0..limit)
+                .find(|&d| cell - at(line as i64 + toward as i64 * d as i64, s) < contrast)
+                .unwrap_or(limit)
+        })
+        .collect();
+    extents.sort_unstable();
+    extents[1]
 }
 
 // fn is_white(pixel: Rgba<u8>) -> bool {
@@ -201,53 +847,205 @@ fn is_black(pixel: Rgba<u8>) -> bool {
 //         && (pixel[2] as i16 - b as i16).abs() <= COLOR_TOLERANCE as i16
 // }
 
-fn detect_dominant_color(colors: &[Rgba<u8>]) -> CellColor {
-    // if colors.is_empty() {
-    //     return CellColor::LightGray;
-    // }
-
-    // Count occurrences of each color
-    // let mut color_counts = std::collections::HashMap::new();
-    // for &pixel in colors {
-    //     let color = detect_single_color(pixel).unwrap();
-    //     *color_counts.entry(color).or_insert(0) += 1;
-    // }
-    // dbg!(&color_counts);
-
-    // // Find the most common color
-    // color_counts
-    //     .into_iter()
-    //     .max_by_key(|&(_, count)| count)
-    //     .map(|(color, _)| color)
-    //     .unwrap_or(CellColor::LightGray)
-    // dbg!(&colors[0]);
-    detect_single_color(colors[0]).unwrap()
-}
-
-fn detect_single_color(pixel: Rgba<u8>) -> Result<CellColor, String> {
-    // if is_white(pixel) {
-    //     return CellColor::LightGray;
-    // }
-
-    let mut closest_color = CellColor::LightGray;
-    // min_diff will decrease as we go through the loop, till we find the closest color
-    let mut min_diff = u32::MAX;
-
-    for ((r, g, b), color) in COLOR_DEFINITIONS {
-        let diff = ((pixel[0] as i32 - *r as i32).abs()
-            + (pixel[1] as i32 - *g as i32).abs()
-            + (pixel[2] as i32 - *b as i32).abs()) as u32;
-        if diff < min_diff {
-            min_diff = diff;
-            closest_color = *color;
-        }
-    }
-
-    if min_diff > 100 {
-        Err(format!("Color difference too large: {}", min_diff))
-    } else {
-        Ok(closest_color)
+// per channel median, a few samples that hit an icon or a border don't move it
+fn median_color(colors: &[Rgb<u8>]) -> [f32; 3] {
+    let mut result = [0.0f32; 3];
+    for (c, channel) in result.iter_mut().enumerate() {
+        let mut values: Vec<u8> = colors.iter().map(|pixel| pixel[c]).collect();
+        values.sort_unstable();
+        *channel = values.get(values.len() / 2).copied().unwrap_or(0) as f32;
+    }
+    result
+}
+
+fn rgb_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+// Agglomerative clustering (average linkage): every color starts as its own
+// cluster and the two closest clusters are merged until `k` are left. Returns
+// the cluster of each color and the mean color of each cluster. Boards are at
+// most a few hundred cells, so the simple O(n^3) version is plenty.
+fn cluster_colors(colors: &[[f32; 3]], k: usize) -> (Vec<usize>, Vec<[f32; 3]>) {
+    let mut clusters: Vec<Vec<usize>> = (0..colors.len()).map(|i| vec![i]).collect();
+
+    while clusters.len() > k.max(1) {
+        let mut best = (0, 1, f32::MAX);
+        for a in 0..clusters.len() {
+            for b in a + 1..clusters.len() {
+                let mut total = 0.0;
+                for &i in &clusters[a] {
+                    for &j in &clusters[b] {
+                        total += rgb_distance(colors[i], colors[j]);
+                    }
+                }
+                let linkage = total / (clusters[a].len() * clusters[b].len()) as f32;
+                if linkage < best.2 {
+                    best = (a, b, linkage);
+                }
+            }
+        }
+        let merged = clusters.swap_remove(best.1);
+        clusters[best.0].extend(merged);
+    }
+
+    let mut assignments = vec![0; colors.len()];
+    let mut centroids = Vec::with_capacity(clusters.len());
+    for (id, members) in clusters.iter().enumerate() {
+        for &i in members {
+            assignments[i] = id;
+        }
+        let member_colors: Vec<[f32; 3]> = members.iter().map(|&i| colors[i]).collect();
+        let mut centroid = [0.0f32; 3];
+        for color in &member_colors {
+            for c in 0..3 {
+                centroid[c] += color[c] / member_colors.len() as f32;
+            }
+        }
+        centroids.push(centroid);
     }
+    (assignments, centroids)
+}
+
+// Gives every cluster a palette color, the closest pairs (by Delta E 2000)
+// first so two clusters never share a name. Clusters too far from any palette
+// entry, or about as close to two of them, are reported. The closest color
+// that's still free is used either way.
+fn label_clusters(
+    centroids: &[[f32; 3]],
+    palette: &[((u8, u8, u8), CellColor)],
+) -> Result<Vec<ClusterLabel>, String> {
+    let palette_lab: Vec<[f32; 3]> = palette
+        .iter()
+        .map(|((r, g, b), _)| rgb_to_lab([*r as f32, *g as f32, *b as f32]))
+        .collect();
+
+    let mut pairs = Vec::new();
+    let mut ambiguous = vec![false; centroids.len()];
+    for (cluster, &centroid) in centroids.iter().enumerate() {
+        match detect_single_color(centroid, palette) {
+            Ok(found) if found.is_ambiguous() => {
+                ambiguous[cluster] = true;
+                let (second, second_distance) = found.runner_up.unwrap_or((found.color, 0.0));
+                println!(
+                    "Cluster {} is ambiguous: {:?} (delta E {:.1}) or {:?} (delta E {:.1})",
+                    cluster, found.color, found.distance, second, second_distance
+                );
+            }
+            Ok(_) => {}
+            Err(e) => println!(
+                "Cluster {} has a color that's not in the palette {:?}: {}",
+                cluster, centroid, e
+            ),
+        }
+        let lab = rgb_to_lab(centroid);
+        for (entry, &entry_lab) in palette_lab.iter().enumerate() {
+            pairs.push((delta_e_2000(lab, entry_lab), cluster, entry));
+        }
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut labels: Vec<Option<CellColor>> = vec![None; centroids.len()];
+    let mut used = vec![false; palette.len()];
+    for (_, cluster, entry) in pairs {
+        if labels[cluster].is_none() && !used[entry] {
+            labels[cluster] = Some(palette[entry].1);
+            used[entry] = true;
+        }
+    }
+    // more regions than palette entries, naming the leftovers after a color
+    // that's already taken would merge two regions
+    let labels = labels
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            format!(
+                "{} regions but only {} palette colors to name them, use the region ids instead",
+                centroids.len(),
+                palette.len()
+            )
+        })?;
+
+    // the runner-up is whatever is closest after the color the cluster got,
+    // which isn't always the closest one when that was taken by another cluster
+    Ok(labels
+        .into_iter()
+        .zip(centroids)
+        .zip(ambiguous)
+        .map(|((color, &centroid), ambiguous)| {
+            let lab = rgb_to_lab(centroid);
+            let runner_up = palette
+                .iter()
+                .zip(&palette_lab)
+                .filter(|((_, entry), _)| *entry != color)
+                .map(|((_, entry), &entry_lab)| (delta_e_2000(lab, entry_lab), *entry))
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, entry)| entry);
+            ClusterLabel {
+                color,
+                runner_up,
+                ambiguous,
+            }
+        })
+        .collect())
+}
+
+// The name a cluster got, see `CellReading`
+#[derive(Debug, Clone, Copy)]
+struct ClusterLabel {
+    color: CellColor,
+    runner_up: Option<CellColor>,
+    ambiguous: bool,
+}
+
+// The closest palette color to some color and how far it is (Delta E 2000),
+// with the second closest to tell how sure that is
+#[derive(Debug, Clone, Copy)]
+struct ColorMatch {
+    color: CellColor,
+    distance: f32,
+    runner_up: Option<(CellColor, f32)>,
+}
+
+impl ColorMatch {
+    // a bit of noise or another display could tip it over to the runner-up,
+    // Light Gray and Warm Beige are only ~7.6 apart to begin with
+    fn is_ambiguous(&self) -> bool {
+        self.runner_up
+            .is_some_and(|(_, distance)| distance - self.distance < AMBIGUOUS_DELTA_E)
+    }
+}
+
+fn detect_single_color(
+    rgb: [f32; 3],
+    palette: &[((u8, u8, u8), CellColor)],
+) -> Result<ColorMatch, String> {
+    let lab = rgb_to_lab(rgb);
+    let mut distances: Vec<(f32, CellColor)> = palette
+        .iter()
+        .map(|((r, g, b), color)| {
+            let entry = rgb_to_lab([*r as f32, *g as f32, *b as f32]);
+            (delta_e_2000(lab, entry), *color)
+        })
+        .collect();
+    distances.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let &(distance, color) = distances.first().ok_or("Empty palette")?;
+    if distance > MAX_DELTA_E {
+        return Err(format!(
+            "Color difference too large: delta E {:.1} to {:?}",
+            distance, color
+        ));
+    }
+    Ok(ColorMatch {
+        color,
+        distance,
+        runner_up: distances.get(1).map(|&(distance, color)| (color, distance)),
+    })
 }
 
 // fn remove_close_lines(lines: &[u32], min_distance: u32) -> Vec<u32> {
@@ -269,6 +1067,152 @@ fn detect_single_color(pixel: Rgba<u8>) -> Result<CellColor, String> {
 //     result
 // }
 
+// The dark theme has a dark page around the board, with the grid lines darker
+// still (the darkest pixels of a board are its lines). An image cropped right
+// at the board has the black border all around it, that's not a dark page.
+pub fn detect_theme(image: &DynamicImage) -> Theme {
+    luma_theme(&luma_buffer(image))
+}
+
+fn luma_theme(luma: &GrayImage) -> Theme {
+    let Some(background) = frame_luma(luma) else {
+        return Theme::Light;
+    };
+    let line_luma = luma_percentile(&luma_histogram(luma), 0.01) as u8;
+
+    if background < DARK_BACKGROUND_LUMA && background > line_luma.saturating_add(10) {
+        Theme::Dark
+    } else {
+        Theme::Light
+    }
+}
+
+// Fallback for when `detect_lines` misses lines (too faint, blurred away or
+// not drawn at all): the board is whatever isn't page, and every cell is one
+// flat color. The smallest n whose cells all come out flat is the grid,
+// fewer cells than that each span a border (or a faint line), more cells
+// (2n, 3n, ..) are flat too but only because they split the real ones.
+// Returns evenly spaced horizontal and vertical lines.
+fn infer_grid_lines(rgb: &RgbImage, calibration: &Calibration) -> Option<(Vec<u32>, Vec<u32>)> {
+    let (width, height) = rgb.dimensions();
+    if width < 2 || height < 2 {
+        return None;
+    }
+    let (top, bottom, left, right) = board_bounds(rgb)?;
+    let min_n = (calibration.min_grid_size as usize)
+        .saturating_sub(1)
+        .max(2);
+    let max_n = MAX_GRID_SIZE.min((bottom - top).min(right - left) / 4);
+
+    let lines = |start: usize, end: usize, n: usize| -> Vec<u32> {
+        (0..=n)
+            .map(|k| (start as f32 + k as f32 * (end - start) as f32 / n as f32).round() as u32)
+            .collect()
+    };
+    let mut best = 0.0f32;
+    for n in min_n..=max_n {
+        let (horizontal, vertical) = (lines(top, bottom, n), lines(left, right, n));
+        let flat = flat_cell_share(rgb, &horizontal, &vertical, calibration);
+        if flat >= MIN_FLAT_CELLS {
+            return Some((horizontal, vertical));
+        }
+        best = best.max(flat);
+    }
+    println!(
+        "No grid of flat cells either, at best {:.0}% of the cells are one color",
+        best * 100.0
+    );
+    None
+}
+
+// The outer edges of the board (top, bottom, left, right), the first and last
+// row and column that are mostly not the page. An image without a page
+// around the board (the outermost pixels aren't one color) is all board.
+fn board_bounds(rgb: &RgbImage) -> Option<(usize, usize, usize, usize)> {
+    let (width, height) = rgb.dimensions();
+    let mut frame = Vec::with_capacity(2 * (width + height) as usize);
+    for x in 0..width {
+        frame.push(*rgb.get_pixel(x, 0));
+        frame.push(*rgb.get_pixel(x, height - 1));
+    }
+    for y in 0..height {
+        frame.push(*rgb.get_pixel(0, y));
+        frame.push(*rgb.get_pixel(width - 1, y));
+    }
+    let page = median_color(&frame);
+    let is_page = |pixel: &Rgb<u8>| {
+        rgb_distance([pixel[0] as f32, pixel[1] as f32, pixel[2] as f32], page) < PAGE_DISTANCE
+    };
+    let page_share =
+        frame.iter().filter(|pixel| is_page(pixel)).count() as f32 / frame.len() as f32;
+    if page_share < MIN_FLAT_CELLS {
+        return Some((0, height as usize - 1, 0, width as usize - 1));
+    }
+
+    let mut rows = vec![0u32; height as usize];
+    let mut columns = vec![0u32; width as usize];
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        if !is_page(pixel) {
+            rows[y as usize] += 1;
+            columns[x as usize] += 1;
+        }
+    }
+    // a line of text on the page is a lot shorter than the board
+    let span = |counts: &[u32]| {
+        let peak = *counts.iter().max()?;
+        let first = counts.iter().position(|&count| count > peak / 2)?;
+        let last = counts.iter().rposition(|&count| count > peak / 2)?;
+        (peak > 0 && last > first).then_some((first, last))
+    };
+    let (top, bottom) = span(&rows)?;
+    let (left, right) = span(&columns)?;
+    Some((top, bottom, left, right))
+}
+
+// share of the cells between the lines where the sample ring (as in
+// `sample_points`) is all about one color
+fn flat_cell_share(
+    rgb: &RgbImage,
+    horizontal: &[u32],
+    vertical: &[u32],
+    calibration: &Calibration,
+) -> f32 {
+    let (inset, center) = (calibration.sample_inset, calibration.sample_center);
+    let (mut flat, mut total) = (0, 0);
+    for rows in horizontal.windows(2) {
+        for cols in vertical.windows(2) {
+            let mut samples = Vec::new();
+            for y_step in 0..SAMPLE_STEPS {
+                for x_step in 0..SAMPLE_STEPS {
+                    let fx =
+                        inset + (1.0 - 2.0 * inset) * x_step as f32 / (SAMPLE_STEPS - 1) as f32;
+                    let fy =
+                        inset + (1.0 - 2.0 * inset) * y_step as f32 / (SAMPLE_STEPS - 1) as f32;
+                    if (fx - 0.5).abs() < center && (fy - 0.5).abs() < center {
+                        continue;
+                    }
+                    let x = cols[0] + (fx * (cols[1] - cols[0]) as f32) as u32;
+                    let y = rows[0] + (fy * (rows[1] - rows[0]) as f32) as u32;
+                    samples.push(*rgb.get_pixel(x, y));
+                }
+            }
+            let color = median_color(&samples);
+            let agreeing = samples
+                .iter()
+                .filter(|pixel| {
+                    rgb_distance([pixel[0] as f32, pixel[1] as f32, pixel[2] as f32], color)
+                        < SAMPLE_AGREEMENT
+                })
+                .count();
+            total += 1;
+            if agreeing as f32 >= samples.len() as f32 * MIN_FLAT_CELLS {
+                flat += 1;
+            }
+        }
+    }
+    flat as f32 / total.max(1) as f32
+}
+
 fn calculate_average_gap(lines: &[u32]) -> u32 {
     let mut gaps = Vec::new();
     for window in lines.windows(2) {
@@ -279,70 +1223,646 @@ fn calculate_average_gap(lines: &[u32]) -> u32 {
 }
 
 // Helper function for line detection
-fn detect_lines(image: &DynamicImage, is_horizontal: bool) -> Vec<u32> {
-    let (width, height) = image.dimensions();
-    let mut lines = Vec::new();
-    let mut window = vec![0; WINDOW_SIZE as usize];
-    let mut window_index = 0;
-    let mut in_line = false;
-    let mut line_start = 0;
+//
+// A grid line pixel is darker than the cell background on both sides of it,
+// whatever the actual colors are. Comparing against the neighbourhood instead
+// of checking for pure black keeps working on JPEG noise, anti-aliased or
+// resized screenshots and the thin gray lines between cells.
+// Returns the (start, end) of every line, in pixels across it.
+fn detect_lines(
+    luma: &GrayImage,
+    is_horizontal: bool,
+    page_luma: Option<u8>,
+    calibration: &Calibration,
+) -> Vec<(u32, u32)> {
+    let (width, height) = luma.dimensions();
 
     let (primary_dim, secondary_dim) = if is_horizontal {
         (height, width)
     } else {
         (width, height)
     };
+    // every run of pixels across the lines as one contiguous slice: the rows
+    // for vertical lines, the columns (rows of the transposed image) for
+    // horizontal ones
+    let transposed;
+    let scan_lines: &[u8] = if is_horizontal {
+        transposed = transpose(luma);
+        &transposed
+    } else {
+        luma.as_raw()
+    };
 
-    for p in 0..primary_dim {
-        let mut dark_count = 0;
-        for s in 0..secondary_dim {
-            let (x, y) = if is_horizontal { (s, p) } else { (p, s) };
-            // if is_dark(image.get_pixel(x, y)) {
-            //     dark_count += 1;
-            // }
-            if is_black(image.get_pixel(x, y)) {
-                dark_count += 1;
-            }
-        }
+    let contrast = line_contrast(luma);
+    // how far to look on each side for the cell background, has to get past
+    // half of the thick outer border but stay well inside a cell. A calibrated
+    // border thickness beats the guess from the image size.
+    let reach = match calibration.line_thickness {
+        0 => (width.min(height) / 40).max(4),
+        thickness => thickness.max(4),
+    } as usize;
+    // past the image edge counts as background (or as the page, when there's
+    // a dark one)
+    let edge = page_luma.map_or(-1, |luma| luma as i32);
 
-        window[window_index] = dark_count;
-        window_index = (window_index + 1) % WINDOW_SIZE as usize;
+    // how many pixels of each row (or column) look like part of a line, the
+    // scan lines are independent so they're counted in parallel
+    let primary = primary_dim as usize;
+    let dark_counts = scan_lines
+        .par_chunks(primary)
+        .fold(
+            || (vec![0u32; primary], vec![0u8; primary], vec![0u8; primary]),
+            |(mut counts, mut from_start, mut from_end), line| {
+                block_maxima(line, reach, &mut from_start, &mut from_end);
+                count_line_pixels(
+                    line,
+                    reach,
+                    contrast,
+                    edge,
+                    &from_start,
+                    &from_end,
+                    &mut counts,
+                );
+                (counts, from_start, from_end)
+            },
+        )
+        .map(|(counts, _, _)| counts)
+        .reduce(
+            || vec![0u32; primary],
+            |mut total, counts| {
+                for (total, count) in total.iter_mut().zip(counts) {
+                    *total += count;
+                }
+                total
+            },
+        );
+    // fraction of each row (or column) that looks like part of a line
+    let profile: Vec<f32> = dark_counts
+        .iter()
+        .map(|&count| count as f32 / secondary_dim as f32)
+        .collect();
 
-        let window_avg: u32 = window.iter().sum::<u32>() / WINDOW_SIZE;
-        // to determine when a line starts
-        // if the number of black pixels is greater than 1/4 of the secondary dimension, then a line starts
-        let threshold = secondary_dim as u32 / 4;
-        // to determine when a line ends
-        // if the number of black pixels is less than 1/8 of the secondary dimension, then a line ends
-        let end_threshold = secondary_dim as u32 / 8;
+    // the thresholds follow the strongest line in the image instead of a fixed
+    // share of the width, a line starts above half of it and ends below a quarter
+    let peak = profile.iter().cloned().fold(0.0, f32::max);
+    if peak < MIN_LINE_COVERAGE {
+        return Vec::new();
+    }
+    let threshold = peak / 2.0;
+    let end_threshold = peak / 4.0;
 
-        if window_avg > threshold && !in_line {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    let mut in_line = false;
+    let mut line_start = 0;
+    for p in 0..primary_dim {
+        let value = profile[p as usize];
+        if value > threshold && !in_line {
             in_line = true;
             line_start = p;
-        } else if window_avg <= end_threshold && in_line {
+        } else if value <= end_threshold && in_line {
             in_line = false;
-            let line_middle = line_start + (p - line_start) / 2;
-            lines.push(line_middle);
+            runs.push((line_start, p));
+        }
+    }
+    if in_line {
+        runs.push((line_start, primary_dim));
+    }
+
+    // noise can split a thick line in two, runs closer than the window are
+    // the same line
+    let mut merged: Vec<(u32, u32)> = Vec::new();
+    for (start, end) in runs {
+        match merged.last_mut() {
+            Some(last) if start - last.1 < calibration.window_size => last.1 = end,
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// Something line-like next to the board (the edge of a gray strip at the
+// bottom of the screenshot, a toolbar) shows up as one more line just past the
+// outer border. The grid lines are evenly spaced, so a line at either end
+// that's less than three quarters of a cell from the next one isn't part of
+// the grid.
+fn trim_stray_lines(lines: &mut Vec<u32>) {
+    while lines.len() > 2 {
+        let mut gaps: Vec<u32> = lines.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        let (first, last) = (gaps[0], gaps[gaps.len() - 1]);
+        gaps.sort_unstable();
+        let cell = gaps[gaps.len() / 2];
+        if first < cell * 3 / 4 {
+            lines.remove(0);
+        } else if last < cell * 3 / 4 {
+            lines.pop();
+        } else {
+            break;
+        }
+    }
+}
+
+// the image flipped along its diagonal, row after row of what were its columns
+fn transpose(luma: &GrayImage) -> Vec<u8> {
+    let (width, height) = (luma.width() as usize, luma.height() as usize);
+    let raw = luma.as_raw();
+    let mut transposed = vec![0u8; raw.len()];
+    transposed
+        .par_chunks_mut(height)
+        .enumerate()
+        .for_each(|(x, column)| {
+            for (y, value) in column.iter_mut().enumerate() {
+                *value = raw[y * width + x];
+            }
+        });
+    transposed
+}
+
+// Running maxima of `line` within blocks of `reach` pixels, from the start of
+// each block up to every pixel and from every pixel to the end of its block.
+// Any window of at most `reach` pixels lies in one block or spans the end of
+// one and the start of the next, so its maximum takes two lookups instead of
+// looking at all of it, see `detect_lines`.
+fn block_maxima(line: &[u8], reach: usize, from_start: &mut [u8], from_end: &mut [u8]) {
+    for ((block, start), end) in line
+        .chunks(reach)
+        .zip(from_start.chunks_mut(reach))
+        .zip(from_end.chunks_mut(reach))
+    {
+        let mut max = 0;
+        for (value, out) in block.iter().zip(start) {
+            max = max.max(*value);
+            *out = max;
+        }
+        let mut max = 0;
+        for (value, out) in block.iter().zip(end).rev() {
+            max = max.max(*value);
+            *out = max;
+        }
+    }
+}
+
+// Adds one to `counts` for every pixel of `line` that's darker than the
+// brightest pixel within `reach` on both sides, with `block_maxima` of the
+// line. `edge` is what's past the ends of the line, -1 for nothing.
+fn count_line_pixels(
+    line: &[u8],
+    reach: usize,
+    contrast: i32,
+    edge: i32,
+    from_start: &[u8],
+    from_end: &[u8],
+    counts: &mut [u32],
+) {
+    let len = line.len();
+    // away from the ends both sides are a whole `reach` long, which spans
+    // the end of one block and the start of the next (or is one whole block)
+    let middle = len.saturating_sub(2 * reach);
+    for (((((count, &value), &before_end), &before_start), &after_end), &after_start) in counts
+        [reach.min(len)..]
+        .iter_mut()
+        .zip(&line[reach.min(len)..])
+        .zip(from_end)
+        .zip(&from_start[(reach - 1).min(len)..])
+        .zip(&from_end[(reach + 1).min(len)..])
+        .zip(&from_start[(2 * reach).min(len)..])
+        .take(middle)
+    {
+        let before = before_end.max(before_start) as i32;
+        let after = after_end.max(after_start) as i32;
+        let value = value as i32;
+        *count += (before - value > contrast && after - value > contrast) as u32;
+    }
+
+    // the brightest of line[first..=last], a window that fits in one block
+    // is cut off by an end of the line so it starts or ends with the block
+    let window = |first: usize, last: usize| {
+        if first / reach != last / reach {
+            from_end[first].max(from_start[last]) as i32
+        } else if first.is_multiple_of(reach) {
+            from_start[last] as i32
+        } else {
+            from_end[first] as i32
+        }
+    };
+    let ends = (0..reach.min(len)).chain(len.saturating_sub(reach).max(reach)..len);
+    for p in ends {
+        let before = if p == 0 {
+            -1
+        } else {
+            window(p.saturating_sub(reach), p - 1)
+        };
+        let after = if p + 1 == len {
+            -1
+        } else {
+            window(p + 1, (p + reach).min(len - 1))
+        };
+        let before = if p < reach { before.max(edge) } else { before };
+        let after = if p + reach >= len {
+            after.max(edge)
+        } else {
+            after
+        };
+        let darker = |side: i32| side < 0 || side - line[p] as i32 > contrast;
+        if darker(before) && darker(after) {
+            counts[p] += 1;
+        }
+    }
+}
+
+// How much darker than its surroundings a pixel has to be to count as a line,
+// scaled to the contrast of the image so washed out or dark themed screenshots
+// aren't held to the same bar as crisp ones
+fn line_contrast(luma: &GrayImage) -> i32 {
+    let histogram = luma_histogram(luma);
+    let spread = luma_percentile(&histogram, 0.95) - luma_percentile(&histogram, 0.05);
+    (spread / 10).clamp(8, 30)
+}
+
+fn luma_histogram(luma: &GrayImage) -> [u32; 256] {
+    let mut histogram = [0u32; 256];
+    for &value in luma.as_raw() {
+        histogram[value as usize] += 1;
+    }
+    histogram
+}
+
+// the luminance `fraction` of the way up from the darkest pixel
+fn luma_percentile(histogram: &[u32; 256], fraction: f32) -> i32 {
+    let total: u32 = histogram.iter().sum();
+    let target = (total as f32 * fraction) as u32;
+    let mut seen = 0;
+    for (value, &count) in histogram.iter().enumerate() {
+        seen += count;
+        if seen > target {
+            return value as i32;
+        }
+    }
+    255
+}
+
+// Measures how thick the grid line at `line` is, across the middle half of the
+// cell side between `span.0` and `span.1` (a horizontal line when
+// `is_horizontal`). Adds up how much darker than the cell background each
+// pixel across the line is, so a line that got blurred by scaling or JPEG
+// still weighs the same, and divides that by how dark a solid line pixel is
+// against this background to get the width in pixels.
+fn border_thickness(
+    luma: &GrayImage,
+    line: u32,
+    span: (u32, u32),
+    is_horizontal: bool,
+    reach: u32,
+    contrast: i32,
+    line_luma: f32,
+) -> f32 {
+    let (width, height) = luma.dimensions();
+    let at = |p: u32, s: u32| {
+        let (x, y) = if is_horizontal { (s, p) } else { (p, s) };
+        luma.get_pixel(x.min(width - 1), y.min(height - 1))[0] as f32
+    };
+    let quarter = (span.1 - span.0) / 4;
+    let reach = reach.max(2);
+
+    let mut samples: Vec<f32> = Vec::new();
+    for s in span.0 + quarter..span.1 - quarter {
+        let (from, to) = (line.saturating_sub(reach), line + reach);
+        // the cell background is the darker of the two sides, so a lighter
+        // neighbouring cell doesn't count as line
+        let background = at(from, s).min(at(to, s));
+        let core = (from..=to).map(|p| at(p, s)).fold(f32::MAX, f32::min);
+        if background - core < contrast as f32 {
+            samples.push(0.0);
+            continue;
         }
+        let darkness: f32 = (from..=to).map(|p| (background - at(p, s)).max(0.0)).sum();
+        samples.push(darkness / (background - line_luma).max(contrast as f32));
+    }
+
+    if samples.is_empty() {
+        return 0.0;
     }
+    // median, a crown or an X touching the line only moves a few samples
+    samples.sort_by(|a, b| a.total_cmp(b));
+    samples[samples.len() / 2]
+}
 
-    lines
+// The borders come in two thicknesses, returns the value between them (the
+// biggest jump in the sorted list). None when they're all about the same.
+fn thick_border_split(sorted: &[f32]) -> Option<f32> {
+    let (low, high) = (*sorted.first()?, *sorted.last()?);
+    // thick borders are about twice as thick, a smaller difference is noise
+    if high < low * 1.3 || high - low < 0.3 {
+        return None;
+    }
+    sorted
+        .windows(2)
+        .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+        .map(|pair| (pair[0] + pair[1]) / 2.0)
 }
 
-// Helper function for sampling a single pixel
-fn sample_pixel(image: &DynamicImage, x: u32, y: u32) -> Rgba<u8> {
-    let pixel = image.get_pixel(x, y);
-    // I think we are sure it won't be black or white, so we can just sample the pixel
-    // if !is_black(pixel) && !is_white(pixel) {
-    //     Some(pixel)
-    // } else {
-    //     None
-    // }
-    pixel
+// a `size` * `size` square with its top left corner at (x, y), clipped to the image
+fn fill_rect(image: &mut image::RgbaImage, x: u32, y: u32, size: u32, color: Rgba<u8>) {
+    let (width, height) = image.dimensions();
+    for py in y..(y + size).min(height) {
+        for px in x..(x + size).min(width) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn sample_pixel(image: &RgbImage, x: u32, y: u32) -> Rgb<u8> {
+    let (width, height) = image.dimensions();
+    *image.get_pixel(x.min(width - 1), y.min(height - 1))
 }
 
 pub fn process_image(image_path: &str) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    process_image_with_calibration(image_path, Calibration::default())
+}
+
+pub fn process_image_with_calibration(
+    image_path: &str,
+    calibration: Calibration,
+) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
     let image = image::open(image_path)?;
-    let board_image = BoardImage::new(image)?;
-    Ok(board_image.get_board_colors())
+    let board_image = BoardImage::from_screenshot_with_calibration(image, calibration)?;
+    Ok(board_image.get_board_colors()?)
+}
+
+// Like `process_image`, for a screenshot that's already in memory (an upload,
+// a message attachment, ...). The format comes from the bytes themselves, not
+// from a file extension.
+pub fn process_image_bytes(bytes: &[u8]) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    process_image_bytes_with_calibration(bytes, Calibration::default())
+}
+
+pub fn process_image_bytes_with_calibration(
+    bytes: &[u8],
+    calibration: Calibration,
+) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    let board_image =
+        BoardImage::from_screenshot_with_calibration(decode_image(bytes)?, calibration)?;
+    Ok(board_image.get_board_colors()?)
+}
+
+// Like `process_image_bytes`, reading the whole screenshot from `reader`
+// first, e.g. `process_image_reader(std::io::stdin())`
+pub fn process_image_reader<R: Read>(reader: R) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    process_image_reader_with_calibration(reader, Calibration::default())
+}
+
+pub fn process_image_reader_with_calibration<R: Read>(
+    mut reader: R,
+    calibration: Calibration,
+) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    process_image_bytes_with_calibration(&bytes, calibration)
+}
+
+// Decodes a PNG, JPEG or WebP screenshot (or anything else the image crate
+// recognizes by its magic bytes)
+pub fn decode_image(bytes: &[u8]) -> Result<DynamicImage, Box<dyn Error>> {
+    let format = image::guess_format(bytes)
+        .map_err(|e| format!("Could not tell the image format from its content: {}", e))?;
+    println!("Decoding {:?} image of {} bytes", format, bytes.len());
+    Ok(image::load_from_memory_with_format(bytes, format)?)
+}
+
+// Like `process_image`, but also reads the queens and X marks of a game
+// that's already in progress
+pub fn process_image_state(image_path: &str) -> Result<BoardState, Box<dyn Error>> {
+    let image = image::open(image_path)?;
+    let board_image = BoardImage::from_screenshot(image)?;
+    Ok(board_image.get_board_state()?)
+}
+
+// Like `process_image`, but the regions come from the thick borders instead of
+// the fill colors, for grayscale or color-filtered screenshots
+pub fn process_image_regions(image_path: &str) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+    let image = image::open(image_path)?;
+    let board_image = BoardImage::from_screenshot(image)?;
+    board_image.get_board_regions()
+}
+
+// Like `process_image`, but returns region ids straight from clustering the
+// cell colors, for boards with colors that aren't in `COLOR_DEFINITIONS`
+pub fn process_image_clusters(image_path: &str) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+    let image = image::open(image_path)?;
+    let board_image = BoardImage::from_screenshot(image)?;
+    Ok(board_image.get_color_regions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn centroid((r, g, b): (u8, u8, u8)) -> [f32; 3] {
+        [r as f32, g as f32, b as f32]
+    }
+
+    #[test]
+    fn every_cluster_gets_its_own_palette_color() {
+        let palette = &COLOR_DEFINITIONS[..4];
+        // given in reverse so the order has to come from the colors
+        let centroids: Vec<[f32; 3]> = palette
+            .iter()
+            .rev()
+            .map(|&(rgb, _)| centroid(rgb))
+            .collect();
+        let labels: Vec<CellColor> = label_clusters(&centroids, palette)
+            .unwrap()
+            .iter()
+            .map(|label| label.color)
+            .collect();
+        let expected: Vec<CellColor> = palette.iter().rev().map(|&(_, color)| color).collect();
+        assert_eq!(labels, expected);
+    }
+
+    #[test]
+    fn clusters_between_two_colors_are_ambiguous() {
+        let rgb = |color| {
+            COLOR_DEFINITIONS
+                .iter()
+                .find(|&&(_, c)| c == color)
+                .unwrap()
+                .0
+        };
+        let gray = rgb(CellColor::LightGray);
+        let beige = rgb(CellColor::WarmBeige);
+        let between = [
+            (gray.0 as f32 + beige.0 as f32) / 2.0,
+            (gray.1 as f32 + beige.1 as f32) / 2.0,
+            (gray.2 as f32 + beige.2 as f32) / 2.0,
+        ];
+        let centroids = [between, centroid(rgb(CellColor::Lavender))];
+        let labels = label_clusters(&centroids, COLOR_DEFINITIONS).unwrap();
+
+        // one of the two, and the other one is the runner-up
+        let (first, second) = (labels[0].color, labels[0].runner_up.unwrap());
+        assert!(labels[0].ambiguous);
+        assert!(
+            (first, second) == (CellColor::LightGray, CellColor::WarmBeige)
+                || (first, second) == (CellColor::WarmBeige, CellColor::LightGray),
+            "{:?} {:?}",
+            first,
+            second
+        );
+        // right on its palette color is clear, there's still a runner-up
+        assert_eq!(labels[1].color, CellColor::Lavender);
+        assert!(!labels[1].ambiguous);
+        assert!(
+            labels[1]
+                .runner_up
+                .is_some_and(|color| color != labels[1].color)
+        );
+    }
+
+    #[test]
+    fn more_clusters_than_palette_colors_is_an_error() {
+        let palette = &COLOR_DEFINITIONS[..3];
+        let mut centroids: Vec<[f32; 3]> = palette.iter().map(|&(rgb, _)| centroid(rgb)).collect();
+        centroids.push([20.0, 20.0, 20.0]);
+        assert!(label_clusters(&centroids, palette).is_err());
+    }
+
+    #[test]
+    fn fixture_cells_are_close_to_their_palette_color() {
+        for path in ["image0.png", "imagez.png", "image_04062025.png"] {
+            let board = BoardImage::from_screenshot(image::open(path).unwrap()).unwrap();
+            let reading = board.read_board().unwrap();
+            for row in 0..board.grid_height {
+                for col in 0..board.grid_width {
+                    let median = median_color(&board.sample_cell_colors(row, col));
+                    let found = detect_single_color(median, COLOR_DEFINITIONS).unwrap();
+                    let cell = reading.cells[row as usize][col as usize];
+                    assert_eq!(found.color, cell.color);
+                    // well inside MAX_DELTA_E, the worst fixture cell is ~5 off
+                    assert!(found.distance < 10.0, "{} {} {}", path, row, col);
+                    assert!(!found.is_ambiguous(), "{} {} {}", path, row, col);
+                    assert!(!cell.ambiguous, "{} {} {}", path, row, col);
+                    assert_eq!(cell.runner_up, found.runner_up.map(|(color, _)| color));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cells_start_where_their_own_lines_end() {
+        // lines 7, 1 and 5 pixels thick, centered on 10, 60 and 110
+        let lines = [(10, 3), (60, 0), (110, 2)];
+        let luma = GrayImage::from_fn(121, 121, |x, y| {
+            let on_line = |p: u32| lines.iter().any(|&(c, r)| p + r >= c && p <= c + r);
+            if on_line(x) || on_line(y) {
+                Luma([60])
+            } else {
+                Luma([230])
+            }
+        });
+        let centers = [10, 60, 110];
+        let cells = measure_cells(&luma, &centers, &centers);
+        let rect = cells[0][0];
+        assert_eq!(
+            (rect.left, rect.top, rect.right, rect.bottom),
+            (14, 14, 59, 59)
+        );
+        let rect = cells[1][1];
+        assert_eq!(
+            (rect.left, rect.top, rect.right, rect.bottom),
+            (61, 61, 107, 107)
+        );
+    }
+
+    #[test]
+    fn fixture_cells_stay_between_their_lines() {
+        for path in ["image0.png", "imagez.png", "image_04062025.png"] {
+            let board = BoardImage::from_screenshot(image::open(path).unwrap()).unwrap();
+            let luma = board.image.to_luma8();
+            for (row, cells) in board.cells.iter().enumerate() {
+                for (col, cell) in cells.iter().enumerate() {
+                    assert!(
+                        cell.left > board.vertical_lines[col],
+                        "{} {} {}",
+                        path,
+                        row,
+                        col
+                    );
+                    assert!(cell.right <= board.vertical_lines[col + 1]);
+                    assert!(cell.top > board.horizontal_lines[row]);
+                    assert!(cell.bottom <= board.horizontal_lines[row + 1]);
+                    // the inner edges are already the cell color, not the line
+                    let inside = luma.get_pixel(cell.left + 2, (cell.top + cell.bottom) / 2)[0];
+                    let edge = luma.get_pixel(cell.left, (cell.top + cell.bottom) / 2)[0];
+                    assert!(inside.abs_diff(edge) < 40, "{} {} {}", path, row, col);
+                }
+            }
+        }
+    }
+
+    // what `count_line_pixels` counts, looking at every pixel of both windows
+    fn naive_line_pixels(line: &[u8], reach: usize, contrast: i32, edge: i32) -> Vec<u32> {
+        let at = |i: isize| {
+            if i < 0 || i >= line.len() as isize {
+                edge
+            } else {
+                line[i as usize] as i32
+            }
+        };
+        (0..line.len() as isize)
+            .map(|p| {
+                let reach = reach as isize;
+                let before = (p - reach..p).map(at).max().unwrap();
+                let after = (p + 1..=p + reach).map(at).max().unwrap();
+                let value = line[p as usize] as i32;
+                let darker = |side: i32| side < 0 || side - value > contrast;
+                (darker(before) && darker(after)) as u32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn line_pixels_match_looking_at_every_pixel() {
+        let contrast = 20;
+        // a line pixel, one exactly `contrast` darker than the background (not
+        // a line) and one a step past it (a line), plus the extremes
+        let values = [100u8, 120, 121, 0, 255];
+        // nothing past the ends, or something right at or past the threshold
+        let edges = [-1, 0, 120, 121, 255];
+        let mut seed = 1u32;
+        for reach in 1..=6 {
+            // shorter than one window, between one and two, and longer
+            for len in 1..=3 * reach + 4 {
+                for _ in 0..40 {
+                    let line: Vec<u8> = (0..len)
+                        .map(|_| {
+                            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                            values[(seed >> 16) as usize % values.len()]
+                        })
+                        .collect();
+                    let (mut from_start, mut from_end) = (vec![0u8; len], vec![0u8; len]);
+                    block_maxima(&line, reach, &mut from_start, &mut from_end);
+                    for edge in edges {
+                        let mut counts = vec![0u32; len];
+                        count_line_pixels(
+                            &line,
+                            reach,
+                            contrast,
+                            edge,
+                            &from_start,
+                            &from_end,
+                            &mut counts,
+                        );
+                        assert_eq!(
+                            counts,
+                            naive_line_pixels(&line, reach, contrast, edge),
+                            "{:?} reach {} edge {}",
+                            line,
+                            reach,
+                            edge
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
@@ -1,11 +1,35 @@
+mod batch;
+mod board_locator;
+mod board_renderer;
+mod calibration;
+mod cielab;
 pub mod click_board;
 mod game_logic;
 mod html_parser;
 mod image_processor;
+mod mhtml;
 mod open_browser;
+mod perspective;
+mod recording;
+mod solution_overlay;
+mod sudoku;
+mod tango;
+mod zip;
 
+pub use batch::*;
+pub use board_locator::*;
+pub use board_renderer::*;
+pub use calibration::*;
+pub use cielab::*;
 pub use click_board::*;
 pub use game_logic::*;
 pub use html_parser::*;
 pub use image_processor::*;
+pub use mhtml::*;
 pub use open_browser::*;
+pub use perspective::*;
+pub use recording::*;
+pub use solution_overlay::*;
+pub use sudoku::*;
+pub use tango::*;
+pub use zip::*;
@@ -1,12 +1,86 @@
-use linkedin_queens::{click_board::click_solution_squares, parse_board, queens, start_browser};
+use linkedin_queens::{
+    BoardImage, Calibration, SolutionMarker, batch_report_csv, click_board::click_solution_squares,
+    click_tango_solution, click_zip_path, input_sudoku_solution, parse_board, parse_sudoku_board,
+    parse_tango_board, parse_zip_board, process_directory_with_calibration,
+    process_image_reader_with_calibration, process_image_with_calibration, process_mhtml,
+    process_photo_with_calibration, process_recording_with_calibration, queens,
+    save_solution_image_with_calibration, solve_sudoku, solve_tango, solve_zip, start_browser,
+    start_sudoku_browser, start_tango_browser, start_zip_browser, write_batch_report,
+};
+
+// `QUEENS_DEBUG_OVERLAY=overlay.png cargo run -- image board.png` also writes
+// what was read on top of the board, see `BoardImage::write_debug_overlay`
+const DEBUG_OVERLAY_VAR: &str = "QUEENS_DEBUG_OVERLAY";
+// which saved profile the screenshots are read with, see `Calibration`
+const CALIBRATION_VAR: &str = "QUEENS_CALIBRATION";
 
 #[tokio::main]
 async fn main() {
+    // which game to play, `cargo run -- tango`, queens by default
+    let game = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "queens".to_string());
+    match game.as_str() {
+        "queens" => play_queens().await,
+        "tango" => play_tango().await,
+        "zip" => play_zip().await,
+        "sudoku" => play_sudoku().await,
+        // solve a saved page offline, `cargo run -- mhtml puzzle.mhtml`
+        "mhtml" => match std::env::args().nth(2) {
+            Some(path) => solve_mhtml(&path),
+            None => println!("Usage: mhtml <path to the saved page>"),
+        },
+        // solve a screenshot, `cargo run -- image board.png solved.png`, the
+        // output is optional, add `circle` to draw circles instead of crowns.
+        // `-` reads the screenshot from stdin (without the output image).
+        "image" => match std::env::args().nth(2) {
+            Some(path) => solve_image(&path, std::env::args().nth(3), std::env::args().nth(4)),
+            None => println!("Usage: image <screenshot> [output.png] [crown|circle]"),
+        },
+        // a photo of the screen taken at an angle, `cargo run -- photo board.jpg`
+        "photo" => match std::env::args().nth(2) {
+            Some(path) => solve_photo(&path),
+            None => println!("Usage: photo <photo of the board>"),
+        },
+        // save what a screenshot looks like as a named profile,
+        // `cargo run -- calibrate board.png retina`, then read with it through
+        // `QUEENS_CALIBRATION=retina cargo run -- image other.png`
+        "calibrate" => match (std::env::args().nth(2), std::env::args().nth(3)) {
+            (Some(path), Some(name)) => calibrate(&path, &name),
+            _ => println!("Usage: calibrate <screenshot> <profile name>"),
+        },
+        // read and solve every screenshot in a directory, `cargo run -- batch
+        // screenshots/ report.json`, CSV unless the report ends in .json, on
+        // stdout without one
+        "batch" => match std::env::args().nth(2) {
+            Some(dir) => solve_batch(&dir, std::env::args().nth(3)),
+            None => println!("Usage: batch <directory> [report.csv|report.json]"),
+        },
+        // the moves of a recorded game, `cargo run -- recording session.gif`,
+        // or a directory of numbered screenshots instead of the GIF
+        "recording" => match std::env::args().nth(2) {
+            Some(path) => replay_recording(&path),
+            None => println!("Usage: recording <recording.gif|directory of frames>"),
+        },
+        other => println!(
+            "Unknown game: {} (expected queens, tango, zip, sudoku, mhtml, image, photo, calibrate, batch or recording)",
+            other
+        ),
+    }
+}
+
+async fn play_queens() {
     // Get the board HTML from the browser
     match start_browser().await {
         Ok((driver, board_html)) => {
             // Parse the HTML into a board
-            let board = parse_board(&board_html);
+            let board = match parse_board(&board_html) {
+                Ok(board) => board,
+                Err(report) => {
+                    println!("Error parsing board: {}", report);
+                    return;
+                }
+            };
 
             // Try to solve the queens puzzle
             match queens(&board) {
@@ -23,3 +97,282 @@ async fn main() {
         Err(e) => println!("Error getting board from browser: {}", e),
     }
 }
+
+fn solve_mhtml(path: &str) {
+    match process_mhtml(path) {
+        Ok(board) => match queens(&board) {
+            Ok(result) => println!("Successfully solved the puzzle! {:?}", result),
+            Err(e) => println!("Error solving puzzle: {}", e),
+        },
+        Err(e) => println!("Error reading board from {}: {}", path, e),
+    }
+}
+
+fn solve_image(path: &str, output: Option<String>, marker: Option<String>) {
+    if path == "-" {
+        match process_image_reader_with_calibration(std::io::stdin(), calibration_from_env()) {
+            Ok(board) => match queens(&board) {
+                Ok(result) => println!("Successfully solved the puzzle! {:?}", result),
+                Err(e) => println!("Error solving puzzle: {}", e),
+            },
+            Err(e) => println!("Error reading board from stdin: {}", e),
+        }
+        return;
+    }
+
+    if let Ok(overlay) = std::env::var(DEBUG_OVERLAY_VAR) {
+        write_debug_overlay(path, &overlay);
+    }
+
+    let Some(output) = output else {
+        match process_image_with_calibration(path, calibration_from_env()) {
+            Ok(board) => match queens(&board) {
+                Ok(result) => println!("Successfully solved the puzzle! {:?}", result),
+                Err(e) => println!("Error solving puzzle: {}", e),
+            },
+            Err(e) => println!("Error reading board from {}: {}", path, e),
+        }
+        return;
+    };
+
+    let marker = match marker.as_deref() {
+        Some("circle") => SolutionMarker::Circle,
+        _ => SolutionMarker::Crown,
+    };
+    match save_solution_image_with_calibration(path, &output, marker, calibration_from_env()) {
+        Ok(result) => println!("Successfully solved the puzzle! {:?}", result),
+        Err(e) => println!("Error solving {}: {}", path, e),
+    }
+}
+
+fn write_debug_overlay(path: &str, overlay: &str) {
+    let board = match image::open(path) {
+        Ok(image) => BoardImage::from_screenshot_with_calibration(image, calibration_from_env()),
+        Err(e) => Err(e.into()),
+    };
+    match board.and_then(|board| board.write_debug_overlay(overlay)) {
+        Ok(()) => println!("Wrote the debug overlay to {}", overlay),
+        Err(e) => println!("Error writing the debug overlay to {}: {}", overlay, e),
+    }
+}
+
+fn solve_photo(path: &str) {
+    match process_photo_with_calibration(path, calibration_from_env()) {
+        Ok(board) => match queens(&board) {
+            Ok(result) => println!("Successfully solved the puzzle! {:?}", result),
+            Err(e) => println!("Error solving puzzle: {}", e),
+        },
+        Err(e) => println!("Error reading board from {}: {}", path, e),
+    }
+}
+
+// The profile named by `QUEENS_CALIBRATION`, or the compiled in defaults
+// when it isn't set or can't be loaded
+fn calibration_from_env() -> Calibration {
+    let Ok(name) = std::env::var(CALIBRATION_VAR) else {
+        return Calibration::default();
+    };
+    match Calibration::load_named(&name) {
+        Ok(calibration) => {
+            println!("Using calibration profile {}", calibration.name);
+            calibration
+        }
+        Err(e) => {
+            println!(
+                "Error loading calibration profile {}: {}, using the defaults",
+                name, e
+            );
+            Calibration::default()
+        }
+    }
+}
+
+fn calibrate(path: &str, name: &str) {
+    let image = match image::open(path) {
+        Ok(image) => image,
+        Err(e) => {
+            println!("Error opening {}: {}", path, e);
+            return;
+        }
+    };
+    // measured with the defaults, not with whatever profile is active
+    let board = match BoardImage::from_screenshot_with_calibration(image, Calibration::default()) {
+        Ok(board) => board,
+        Err(e) => {
+            println!("Error reading board from {}: {}", path, e);
+            return;
+        }
+    };
+    let profile = match board.calibration(name) {
+        Ok(profile) => profile,
+        Err(e) => {
+            println!("Error reading board from {}: {}", path, e);
+            return;
+        }
+    };
+    match profile.save_named() {
+        Ok(profile) => println!(
+            "Saved calibration profile {} to {}",
+            name,
+            profile.display()
+        ),
+        Err(e) => println!("Error saving calibration profile {}: {}", name, e),
+    }
+}
+
+fn solve_batch(dir: &str, report: Option<String>) {
+    let results = match process_directory_with_calibration(dir, calibration_from_env()) {
+        Ok(results) => results,
+        Err(e) => {
+            println!("Error reading {}: {}", dir, e);
+            return;
+        }
+    };
+
+    let solved = results.iter().filter(|r| r.solution.is_some()).count();
+    let unique = results.iter().filter(|r| r.unique == Some(true)).count();
+    println!(
+        "Solved {} of {} boards, {} with a unique solution",
+        solved,
+        results.len(),
+        unique
+    );
+    for result in &results {
+        if let Some(error) = &result.error {
+            println!("{}: {}", result.path.display(), error);
+        }
+    }
+
+    match report {
+        Some(path) => match write_batch_report(&results, &path) {
+            Ok(()) => println!("Wrote the report to {}", path),
+            Err(e) => println!("Error writing the report to {}: {}", path, e),
+        },
+        None => print!("{}", batch_report_csv(&results)),
+    }
+}
+
+fn replay_recording(path: &str) {
+    let recording = match process_recording_with_calibration(path, calibration_from_env()) {
+        Ok(recording) => recording,
+        Err(e) => {
+            println!("Error reading the recording {}: {}", path, e);
+            return;
+        }
+    };
+
+    for step in &recording.moves {
+        let time = step
+            .time
+            .map(|time| format!(" at {:.1}s", time.as_secs_f32()))
+            .unwrap_or_default();
+        println!(
+            "frame {}{}: row {}, column {}: {:?} -> {:?}",
+            step.frame,
+            time,
+            step.row + 1,
+            step.col + 1,
+            step.from,
+            step.to
+        );
+    }
+
+    // did the player get there
+    let queens_placed: Vec<usize> = recording
+        .final_queens()
+        .iter()
+        .map(|(row, col)| row * recording.colors.len() + col)
+        .collect();
+    match queens(&recording.colors) {
+        Ok(solution) if solution == queens_placed => println!("The recording ends solved"),
+        Ok(solution) => println!(
+            "The recording ends unsolved, queens on {:?}, the solution is {:?}",
+            queens_placed, solution
+        ),
+        Err(e) => println!("Error solving the recorded board: {}", e),
+    }
+}
+
+async fn play_tango() {
+    match start_tango_browser().await {
+        Ok((driver, board_html)) => {
+            let board = match parse_tango_board(&board_html) {
+                Ok(board) => board,
+                Err(e) => {
+                    println!("Error parsing tango board: {}", e);
+                    return;
+                }
+            };
+
+            match solve_tango(&board) {
+                Ok(result) => {
+                    println!("Successfully solved the puzzle! {:?}", result);
+                    if let Err(e) = click_tango_solution(&driver, &board, &result).await {
+                        println!("Error clicking solution squares: {}", e);
+                    }
+                }
+                Err(e) => println!("Error solving puzzle: {}", e),
+            }
+        }
+        Err(e) => println!("Error getting board from browser: {}", e),
+    }
+}
+
+async fn play_zip() {
+    match start_zip_browser().await {
+        Ok((driver, board_html)) => {
+            let board = match parse_zip_board(&board_html) {
+                Ok(board) => board,
+                Err(e) => {
+                    println!("Error parsing zip board: {}", e);
+                    return;
+                }
+            };
+
+            match solve_zip(&board) {
+                Ok(path) => {
+                    println!("Successfully solved the puzzle! {:?}", path);
+                    if let Err(e) = click_zip_path(&driver, &path).await {
+                        println!("Error drawing the path: {}", e);
+                    }
+                }
+                Err(e) => println!("Error solving puzzle: {}", e),
+            }
+        }
+        Err(e) => println!("Error getting board from browser: {}", e),
+    }
+}
+
+async fn play_sudoku() {
+    match start_sudoku_browser().await {
+        Ok((driver, board_html)) => {
+            let board = match parse_sudoku_board(&board_html) {
+                Ok(board) => board,
+                Err(e) => {
+                    println!("Error parsing sudoku board: {}", e);
+                    return;
+                }
+            };
+
+            match solve_sudoku(&board) {
+                Ok(solution) => {
+                    println!("Successfully solved the puzzle!");
+                    for step in &solution.steps {
+                        println!(
+                            "row {}, column {}: {} ({:?})",
+                            step.row + 1,
+                            step.col + 1,
+                            step.digit,
+                            step.reason
+                        );
+                    }
+                    if let Err(e) = input_sudoku_solution(&driver, &board, &solution).await {
+                        println!("Error entering the digits: {}", e);
+                    }
+                }
+                Err(e) => println!("Error solving puzzle: {}", e),
+            }
+        }
+        Err(e) => println!("Error getting board from browser: {}", e),
+    }
+}
@@ -0,0 +1,270 @@
+use std::error::Error;
+
+use crate::game_logic::CellColor;
+use crate::html_parser::parse_board;
+
+// "Save page as" -> "Webpage, Single File" writes a MIME multipart/related
+// archive, the page HTML is one of the parts, usually quoted-printable encoded,
+// and the images/css are the other parts, usually base64
+
+struct MimePart {
+    content_type: String,
+    encoding: String,
+    body: String,
+}
+
+// Reads a saved page from disk and parses the queens board in it. Plain .html
+// files work too, they just don't have any MIME parts to decode.
+pub fn process_mhtml(path: &str) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    let content = std::fs::read(path)?;
+    let html = extract_board_html(&String::from_utf8_lossy(&content))?;
+    Ok(parse_board(&html)?)
+}
+
+// Returns the decoded HTML of the part that has the `queens-grid` markup
+pub fn extract_board_html(content: &str) -> Result<String, String> {
+    let (headers, body) = split_headers(content);
+    let content_type = header_value(headers, "Content-Type").unwrap_or_default();
+
+    if !content_type.to_lowercase().starts_with("multipart/") {
+        // not an archive, maybe a plain html capture
+        return if content.contains("queens-grid") {
+            Ok(content.to_string())
+        } else {
+            Err("No MIME parts and no queens-grid markup in the file".to_string())
+        };
+    }
+
+    let boundary =
+        header_param(&content_type, "boundary").ok_or("Multipart archive without a boundary")?;
+    let mut parts = Vec::new();
+    collect_parts(body, &boundary, &mut parts);
+    println!("Found {} MIME parts", parts.len());
+
+    for part in &parts {
+        if !part.content_type.to_lowercase().starts_with("text/html") {
+            continue;
+        }
+        let html = String::from_utf8_lossy(&decode_body(&part.body, &part.encoding)?).into_owned();
+        if html.contains("queens-grid") {
+            return Ok(html);
+        }
+    }
+    Err("None of the HTML parts contains the queens-grid markup".to_string())
+}
+
+fn collect_parts(body: &str, boundary: &str, parts: &mut Vec<MimePart>) {
+    let delimiter = format!("--{}", boundary);
+    // the first chunk is the preamble, the one after the closing `--boundary--`
+    // is the epilogue
+    for chunk in body.split(delimiter.as_str()).skip(1) {
+        if chunk.starts_with("--") {
+            break;
+        }
+        let (headers, part_body) = split_headers(chunk.trim_start_matches(['\r', '\n']));
+        let content_type = header_value(headers, "Content-Type").unwrap_or_default();
+
+        // multipart/alternative and friends can be nested inside
+        if content_type.to_lowercase().starts_with("multipart/") {
+            if let Some(nested) = header_param(&content_type, "boundary") {
+                collect_parts(part_body, &nested, parts);
+            }
+            continue;
+        }
+
+        parts.push(MimePart {
+            content_type,
+            encoding: header_value(headers, "Content-Transfer-Encoding")
+                .unwrap_or_default()
+                .to_lowercase(),
+            body: part_body.to_string(),
+        });
+    }
+}
+
+// splits at the first empty line, whichever line ending it uses
+fn split_headers(content: &str) -> (&str, &str) {
+    ["\r\n\r\n", "\n\n"]
+        .into_iter()
+        .filter_map(|separator| Some((content.find(separator)?, separator.len())))
+        .min()
+        .map(|(pos, len)| (&content[..pos], &content[pos + len..]))
+        .unwrap_or((content, ""))
+}
+
+fn header_value(headers: &str, name: &str) -> Option<String> {
+    // unfold the headers first, a line starting with whitespace continues the
+    // previous one
+    let mut unfolded: Vec<String> = Vec::new();
+    for line in headers.lines() {
+        match unfolded.last_mut() {
+            Some(last) if line.starts_with([' ', '\t']) => {
+                last.push(' ');
+                last.push_str(line.trim());
+            }
+            _ => unfolded.push(line.trim_end().to_string()),
+        }
+    }
+
+    unfolded.into_iter().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().to_string())
+    })
+}
+
+// `boundary="----abc"` out of `multipart/related; type="text/html"; boundary="----abc"`
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn decode_body(body: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    match encoding {
+        "quoted-printable" => Ok(decode_quoted_printable(body)),
+        "base64" => decode_base64(body),
+        // 7bit, 8bit, binary or nothing at all
+        _ => Ok(body.as_bytes().to_vec()),
+    }
+}
+
+fn decode_quoted_printable(body: &str) -> Vec<u8> {
+    let bytes = body.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            result.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // `=` at the end of a line is a soft line break
+        if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+            continue;
+        }
+        if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+            continue;
+        }
+        // `=XX` is an escaped byte, anything else is kept as is
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                result.push(byte);
+                i += 3;
+            }
+            None => {
+                result.push(b'=');
+                i += 1;
+            }
+        }
+    }
+    result
+}
+
+fn decode_base64(body: &str) -> Result<Vec<u8>, String> {
+    let mut result = Vec::with_capacity(body.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in body.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return Err(format!("Invalid base64 character '{}'", c as char)),
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // html_board1.html saved as a single file: a decoy page, the board in a
+    // nested multipart/alternative (quoted-printable) and a base64 image
+    const ARCHIVE: &str = include_str!("../board1.mhtml");
+
+    #[test]
+    fn board_part_reads_like_the_saved_html() {
+        let html = extract_board_html(ARCHIVE).unwrap();
+        // the line breaks inside the part are CRLF, as MIME has them, and
+        // the blank line before the next boundary stays on the end
+        assert_eq!(
+            html.replace("\r\n", "\n").trim_end(),
+            include_str!("../html_board1.html")
+        );
+        assert_eq!(
+            parse_board(&html).unwrap(),
+            parse_board(include_str!("../html_board1.html")).unwrap()
+        );
+        assert_eq!(
+            process_mhtml("board1.mhtml").unwrap(),
+            parse_board(include_str!("../html_board1.html")).unwrap()
+        );
+    }
+
+    #[test]
+    fn parts_come_from_every_level_of_the_archive() {
+        let (headers, body) = split_headers(ARCHIVE);
+        // the boundary is on a folded line of the header
+        let content_type = header_value(headers, "Content-Type").unwrap();
+        let boundary = header_param(&content_type, "boundary").unwrap();
+        assert_eq!(boundary, "----MultipartBoundary--outer----");
+
+        let mut parts = Vec::new();
+        collect_parts(body, &boundary, &mut parts);
+        let types: Vec<&str> = parts
+            .iter()
+            .map(|part| part.content_type.as_str())
+            .collect();
+        assert_eq!(types, ["text/html", "text/plain", "text/html", "image/png"]);
+
+        let image = decode_body(&parts[3].body, &parts[3].encoding).unwrap();
+        assert!(image.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let image = image::load_from_memory(&image).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 2));
+    }
+
+    #[test]
+    fn quoted_printable_escapes_and_soft_breaks() {
+        let decoded = decode_quoted_printable("class=3D\"a=\r\nb\" x=\ny =C3=A9 =ZZ end=");
+        assert_eq!(
+            String::from_utf8(decoded).unwrap(),
+            "class=\"ab\" xy é =ZZ end="
+        );
+    }
+
+    #[test]
+    fn base64_with_line_breaks_and_url_safe_letters() {
+        assert_eq!(decode_base64("aGVs\r\nbG8=").unwrap(), b"hello");
+        assert_eq!(decode_base64("-_8").unwrap(), [0xfb, 0xff]);
+        assert!(decode_base64("aGVs*").is_err());
+    }
+
+    #[test]
+    fn files_without_the_board_are_an_error() {
+        assert!(extract_board_html("<html></html>").is_err());
+        let archive = ARCHIVE.replace("queens-grid", "tango-grid");
+        assert!(extract_board_html(&archive).is_err());
+    }
+}
@@ -4,6 +4,23 @@ use thirtyfour::error::WebDriverErrorInfo;
 use thirtyfour::prelude::*;
 
 pub async fn start_browser() -> WebDriverResult<(WebDriver, String)> {
+    start_game(
+        "https://www.linkedin.com/games/view/queens/desktop",
+        By::Id("queens-grid"),
+        "div.queens-cell-with-border",
+    )
+    .await
+}
+
+// Opens one of the LinkedIn games, waits for its grid to show up (`grid`
+// matches the grid element and `cell_css` its cells), and returns the driver
+// and the grid's outer HTML. Every game page has the same start button and
+// modal, only the grid markup differs.
+pub async fn start_game(
+    game_url: &str,
+    grid: By,
+    cell_css: &str,
+) -> WebDriverResult<(WebDriver, String)> {
     // Define the base user data directory and the specific profile directory
     // These paths must exactly match how Edge stores them on your system.
     // Use `edge://version/` in your Edge browser to confirm.
@@ -40,10 +57,8 @@ pub async fn start_browser() -> WebDriverResult<(WebDriver, String)> {
     //     .set_implicit_wait_timeout(Duration::from_secs(1))
     //     .await?;
 
-    // Navigate to LinkedIn Queens game
-    driver
-        .goto("https://www.linkedin.com/games/view/queens/desktop")
-        .await?;
+    // Navigate to the LinkedIn game
+    driver.goto(game_url).await?;
 
     // Wait for and click the start game button with aggressive polling
     let mut attempts = 0;
@@ -79,17 +94,14 @@ pub async fn start_browser() -> WebDriverResult<(WebDriver, String)> {
     let mut attempts = 0;
     let max_attempts = i32::MAX;
     let board = loop {
-        match driver.find(By::Id("queens-grid")).await {
+        match driver.find(grid.clone()).await {
             Ok(board_element) => {
                 // Check if the board has content immediately
-                if let Ok(cells) = board_element
-                    .find_all(By::Css("div.queens-cell-with-border"))
-                    .await
+                if let Ok(cells) = board_element.find_all(By::Css(cell_css)).await
+                    && !cells.is_empty()
                 {
-                    if !cells.is_empty() {
-                        println!("Game board and cells found. Board is loaded.");
-                        break board_element;
-                    }
+                    println!("Game board and cells found. Board is loaded.");
+                    break board_element;
                 }
                 attempts += 1;
                 if attempts >= max_attempts {
@@ -108,7 +120,7 @@ pub async fn start_browser() -> WebDriverResult<(WebDriver, String)> {
                     // If we can't find the board at all after max attempts, return error
                     return Err(WebDriverError::NoSuchElement(WebDriverErrorInfo::new(
                         format!(
-                            "Could not find queens-grid element after {} attempts: {}",
+                            "Could not find game grid element after {} attempts: {}",
                             max_attempts, e
                         ),
                     )));
@@ -127,3 +139,10 @@ pub async fn start_browser() -> WebDriverResult<(WebDriver, String)> {
     // The driver is not quit here, so the browser stays open for inspection.
     Ok((driver, board_html))
 }
+
+// The solvers are done once the last click went through, this only keeps the
+// browser open for a while so the finished board can be looked at before the
+// driver goes away.
+pub async fn keep_browser_open() {
+    tokio::time::sleep(tokio::time::Duration::from_secs(59)).await;
+}
@@ -0,0 +1,160 @@
+use std::error::Error;
+
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+use crate::board_locator::find_grid_corners;
+use crate::calibration::Calibration;
+use crate::game_logic::CellColor;
+use crate::image_processor::BoardImage;
+
+// Photos of a monitor or a phone show the board at an angle, so the square
+// grid ends up as some quadrilateral. Mapping its four corners back onto a
+// square (a homography) straightens it out for the usual line detection.
+
+// Finds the board in a photo and warps it to an upright square. The side is
+// the longest edge of the photographed grid, so no detail is lost.
+pub fn correct_perspective(image: &DynamicImage) -> Option<DynamicImage> {
+    let corners = find_grid_corners(image)?;
+    println!("Found grid corners at {:?}", corners);
+
+    let side = (0..4)
+        .map(|i| distance(corners[i], corners[(i + 1) % 4]))
+        .fold(0.0, f32::max)
+        .round() as u32;
+    Some(warp_to_square(image, corners, side))
+}
+
+// Warps the quadrilateral `corners` (top left, top right, bottom right, bottom
+// left) to a `side` * `side` square, with a margin around it so the outer
+// border is still surrounded by background
+pub fn warp_to_square(image: &DynamicImage, corners: [(f32, f32); 4], side: u32) -> DynamicImage {
+    let margin = (side / 50).max(4);
+    let size = side + 2 * margin;
+    let (start, end) = (margin as f32, (margin + side) as f32);
+    let square = [(start, start), (end, start), (end, end), (start, end)];
+
+    // every output pixel looks up where it came from, so map square -> photo
+    let Some(h) = homography(square, corners) else {
+        println!("Grid corners are degenerate, keeping the image as it is");
+        return image.clone();
+    };
+    let mut output = RgbaImage::new(size, size);
+    for y in 0..size {
+        for x in 0..size {
+            let (sx, sy) = project(&h, x as f32 + 0.5, y as f32 + 0.5);
+            output.put_pixel(x, y, sample_bilinear(image, sx - 0.5, sy - 0.5));
+        }
+    }
+    DynamicImage::ImageRgba8(output)
+}
+
+impl BoardImage {
+    // Same as `from_screenshot`, but the board may be photographed at an angle
+    pub fn from_photo(image: DynamicImage) -> Result<Self, Box<dyn Error>> {
+        Self::from_photo_with_calibration(image, Calibration::default())
+    }
+
+    pub fn from_photo_with_calibration(
+        image: DynamicImage,
+        calibration: Calibration,
+    ) -> Result<Self, Box<dyn Error>> {
+        match correct_perspective(&image) {
+            Some(square) => Self::new_with_calibration(square, calibration),
+            None => {
+                println!("Could not find the grid corners, using the whole image");
+                Self::new_with_calibration(image, calibration)
+            }
+        }
+    }
+}
+
+// Like `process_image`, for photos of a screen instead of screenshots
+pub fn process_photo(image_path: &str) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    process_photo_with_calibration(image_path, Calibration::default())
+}
+
+pub fn process_photo_with_calibration(
+    image_path: &str,
+    calibration: Calibration,
+) -> Result<Vec<Vec<CellColor>>, Box<dyn Error>> {
+    let image = image::open(image_path)?;
+    let board_image = BoardImage::from_photo_with_calibration(image, calibration)?;
+    Ok(board_image.get_board_colors()?)
+}
+
+// The 3x3 matrix (last entry fixed to 1) mapping each `from` point onto the
+// matching `to` point. Each pair gives two linear equations for the other
+// eight entries. None if three of the points are on one line.
+fn homography(from: [(f32, f32); 4], to: [(f32, f32); 4]) -> Option<[f64; 9]> {
+    let mut system = [[0.0f64; 9]; 8];
+    for (i, (&(x, y), &(u, v))) in from.iter().zip(to.iter()).enumerate() {
+        let (x, y, u, v) = (x as f64, y as f64, u as f64, v as f64);
+        system[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+        system[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+    }
+
+    // gaussian elimination with partial pivoting, the last column is the
+    // right hand side
+    for col in 0..8 {
+        let pivot =
+            (col..8).max_by(|&a, &b| system[a][col].abs().total_cmp(&system[b][col].abs()))?;
+        if system[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+        system.swap(col, pivot);
+        let pivot_row = system[col];
+        for (row, equation) in system.iter_mut().enumerate() {
+            if row == col {
+                continue;
+            }
+            let factor = equation[col] / pivot_row[col];
+            for (value, pivot_value) in equation.iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+
+    let mut h = [1.0f64; 9];
+    for (i, entry) in h.iter_mut().take(8).enumerate() {
+        *entry = system[i][8] / system[i][i];
+    }
+    Some(h)
+}
+
+fn project(h: &[f64; 9], x: f32, y: f32) -> (f32, f32) {
+    let (x, y) = (x as f64, y as f64);
+    let w = h[6] * x + h[7] * y + h[8];
+    (
+        ((h[0] * x + h[1] * y + h[2]) / w) as f32,
+        ((h[3] * x + h[4] * y + h[5]) / w) as f32,
+    )
+}
+
+// blends the four pixels around (x, y), coordinates outside the image take
+// the nearest edge pixel
+fn sample_bilinear(image: &DynamicImage, x: f32, y: f32) -> Rgba<u8> {
+    let (width, height) = image.dimensions();
+    let x = x.clamp(0.0, (width - 1) as f32);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let corners = [
+        (image.get_pixel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+        (image.get_pixel(x1, y0), fx * (1.0 - fy)),
+        (image.get_pixel(x0, y1), (1.0 - fx) * fy),
+        (image.get_pixel(x1, y1), fx * fy),
+    ];
+    let mut result = [0.0f32; 4];
+    for (pixel, weight) in corners {
+        for (channel, value) in result.iter_mut().enumerate() {
+            *value += pixel[channel] as f32 * weight;
+        }
+    }
+    Rgba(result.map(|value| value.round() as u8))
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
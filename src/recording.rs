@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, DynamicImage, ImageFormat};
+
+use crate::calibration::Calibration;
+use crate::game_logic::CellColor;
+use crate::image_processor::{BoardImage, CellMark};
+
+const ICON_ANIMATION: Duration = Duration::from_millis(300); // How long a crown takes to grow or shrink
+
+// A solving session read back from a recording: the board as it was before
+// the first move and every change the player made after that
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub colors: Vec<Vec<CellColor>>,
+    // the frame the empty board was read from
+    pub start_frame: usize,
+    pub moves: Vec<Move>,
+    // the marks on the last frame the board was readable on
+    pub final_marks: Vec<Vec<CellMark>>,
+}
+
+// One cell changing between two frames. Taking a queen or an X back is a move
+// to `CellMark::Empty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub frame: usize,
+    // since the start of the recording, None for frames without timing (a
+    // directory of screenshots)
+    pub time: Option<Duration>,
+    pub row: usize,
+    pub col: usize,
+    pub from: CellMark,
+    pub to: CellMark,
+}
+
+impl Recording {
+    // (row, col) of the queens on the board at the end
+    pub fn final_queens(&self) -> Vec<(usize, usize)> {
+        let mut queens = Vec::new();
+        for (row, marks) in self.final_marks.iter().enumerate() {
+            for (col, mark) in marks.iter().enumerate() {
+                if *mark == CellMark::Queen {
+                    queens.push((row, col));
+                }
+            }
+        }
+        queens
+    }
+}
+
+// a frame and when it's shown
+type Frame = (DynamicImage, Option<Duration>);
+
+// Reads an animated GIF, or a directory with one screenshot per frame (in
+// file name order, so number them with leading zeros)
+pub fn process_recording(path: &str) -> Result<Recording, Box<dyn Error>> {
+    process_recording_with_calibration(path, Calibration::default())
+}
+
+pub fn process_recording_with_calibration(
+    path: &str,
+    calibration: Calibration,
+) -> Result<Recording, Box<dyn Error>> {
+    if Path::new(path).is_dir() {
+        analyze_frames_with_calibration(directory_frames(path)?, calibration)
+    } else {
+        analyze_frames_with_calibration(gif_frames(path)?, calibration)
+    }
+}
+
+// The frames are decoded one at a time, a long recording doesn't have to fit
+// in memory
+pub fn gif_frames(
+    path: &str,
+) -> Result<impl Iterator<Item = Result<Frame, Box<dyn Error>>>, Box<dyn Error>> {
+    let decoder = GifDecoder::new(BufReader::new(File::open(path)?))?;
+    let mut elapsed = Duration::ZERO;
+    Ok(decoder.into_frames().map(move |frame| {
+        let frame = frame?;
+        let time = elapsed;
+        let (numerator, denominator) = frame.delay().numer_denom_ms();
+        elapsed += Duration::from_secs_f64(numerator as f64 / denominator.max(1) as f64 / 1000.0);
+        Ok((DynamicImage::ImageRgba8(frame.into_buffer()), Some(time)))
+    }))
+}
+
+pub fn directory_frames(
+    dir: &str,
+) -> Result<impl Iterator<Item = Result<Frame, Box<dyn Error>>>, Box<dyn Error>> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && ImageFormat::from_path(&path).is_ok() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    println!("Found {} frames in {}", paths.len(), dir);
+    Ok(paths
+        .into_iter()
+        .map(|path| Ok((image::open(&path)?, None))))
+}
+
+// The first frame with a complete, empty board gives the colors and where
+// the cells are, the frames after it are only checked for marks at those same
+// places. Frames the board can't be read on are skipped.
+pub fn analyze_frames(
+    frames: impl Iterator<Item = Result<Frame, Box<dyn Error>>>,
+) -> Result<Recording, Box<dyn Error>> {
+    analyze_frames_with_calibration(frames, Calibration::default())
+}
+
+pub fn analyze_frames_with_calibration(
+    frames: impl Iterator<Item = Result<Frame, Box<dyn Error>>>,
+    calibration: Calibration,
+) -> Result<Recording, Box<dyn Error>> {
+    let mut board: Option<(BoardImage, Vec<Vec<CellColor>>, usize)> = None;
+    let mut marks: Vec<Vec<CellMark>> = Vec::new();
+    let mut moves: Vec<Move> = Vec::new();
+    let mut previous_frame = 0;
+
+    for (index, frame) in frames.enumerate() {
+        let (image, time) = frame?;
+        let Some((start, _, _)) = &board else {
+            // still looking for the empty board
+            let Ok(found) =
+                BoardImage::from_screenshot_with_calibration(image, calibration.clone())
+            else {
+                continue;
+            };
+            let found_marks = found.detect_marks();
+            if found_marks
+                .iter()
+                .flatten()
+                .all(|mark| *mark == CellMark::Empty)
+            {
+                println!("Found the empty board on frame {}", index);
+                let colors = found.get_board_colors()?;
+                marks = found_marks;
+                previous_frame = index;
+                board = Some((found, colors, index));
+            }
+            continue;
+        };
+
+        let Some(current) = start.same_board_in(&image) else {
+            println!("Frame {} doesn't have the board, skipping it", index);
+            continue;
+        };
+        let frame_marks = current.detect_marks();
+        for (row, (before, after)) in marks.iter().zip(&frame_marks).enumerate() {
+            for (col, (&from, &to)) in before.iter().zip(after).enumerate() {
+                if from == to {
+                    continue;
+                }
+                // the icons animate, a crown that's still growing (or already
+                // shrinking away) reads as an X for a frame, so an X that was
+                // only on the frame before and turns into something else
+                // before the animation is over is the same move. An X that's
+                // taken back right away, or that stayed longer than that (a
+                // real X and then a queen), stays two moves.
+                let ongoing = moves.iter().rposition(|m| {
+                    m.row == row
+                        && m.col == col
+                        && m.frame == previous_frame
+                        && m.to == CellMark::Cross
+                        && m.from != to
+                        && within_animation(m.time, time)
+                });
+                match ongoing {
+                    Some(i) => {
+                        moves[i].to = to;
+                        moves[i].frame = index;
+                        moves[i].time = time;
+                    }
+                    None => moves.push(Move {
+                        frame: index,
+                        time,
+                        row,
+                        col,
+                        from,
+                        to,
+                    }),
+                }
+            }
+        }
+        marks = frame_marks;
+        previous_frame = index;
+    }
+
+    let (_, colors, start_frame) =
+        board.ok_or("No frame shows the whole board without any marks on it")?;
+    println!("Found {} moves after frame {}", moves.len(), start_frame);
+    Ok(Recording {
+        colors,
+        start_frame,
+        moves,
+        final_marks: marks,
+    })
+}
+
+// whether `to` comes less than an icon animation after `from`, frames without
+// timing can't tell so they never are
+fn within_animation(from: Option<Duration>, to: Option<Duration>) -> bool {
+    match (from, to) {
+        (Some(from), Some(to)) => to.saturating_sub(from) < ICON_ANIMATION,
+        _ => false,
+    }
+}
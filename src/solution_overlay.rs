@@ -0,0 +1,148 @@
+use std::error::Error;
+
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+
+use crate::calibration::Calibration;
+use crate::game_logic::queens;
+use crate::image_processor::BoardImage;
+
+const CROWN_FILL: Rgba<u8> = Rgba([255, 193, 7, 255]);
+const CROWN_OUTLINE: Rgba<u8> = Rgba([40, 40, 40, 255]);
+const CIRCLE_FILL: Rgba<u8> = Rgba([220, 30, 30, 255]);
+
+// what gets drawn on the cells of the solution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolutionMarker {
+    Crown,
+    Circle,
+}
+
+// the crown outline on a 0..1 square, base at the bottom and three spikes
+const CROWN_SHAPE: &[(f32, f32)] = &[
+    (0.1, 0.85),
+    (0.1, 0.25),
+    (0.3, 0.55),
+    (0.5, 0.15),
+    (0.7, 0.55),
+    (0.9, 0.25),
+    (0.9, 0.85),
+];
+
+// Draws a marker on every queen of `solution` (flattened cell indexes, as
+// `queens` returns them) at the cell centers found by the line detection.
+// `image` is the screenshot `board` was read from, so the markers land on the
+// original picture even when the board was cropped out of it.
+pub fn draw_solution(
+    image: &DynamicImage,
+    board: &BoardImage,
+    solution: &[usize],
+    marker: SolutionMarker,
+) -> RgbaImage {
+    let mut output = image.to_rgba8();
+    let n = board.grid_size();
+    let size = board.cell_size();
+
+    for &idx in solution {
+        let (row, col) = (idx / n, idx % n);
+        let (x, y) = board.cell_center(row, col);
+        match marker {
+            SolutionMarker::Crown => draw_crown_marker(&mut output, x, y, size),
+            SolutionMarker::Circle => {
+                draw_circle(&mut output, x, y, size as f32 * 0.3, CIRCLE_FILL);
+            }
+        }
+    }
+    output
+}
+
+// Reads the board from a screenshot, solves it and writes the screenshot with
+// the solution drawn on it to `output_path` as PNG
+pub fn save_solution_image(
+    image_path: &str,
+    output_path: &str,
+    marker: SolutionMarker,
+) -> Result<Vec<usize>, Box<dyn Error>> {
+    save_solution_image_with_calibration(image_path, output_path, marker, Calibration::default())
+}
+
+pub fn save_solution_image_with_calibration(
+    image_path: &str,
+    output_path: &str,
+    marker: SolutionMarker,
+    calibration: Calibration,
+) -> Result<Vec<usize>, Box<dyn Error>> {
+    let image = image::open(image_path)?;
+    let board = BoardImage::from_screenshot_with_calibration(image.clone(), calibration)?;
+    let solution = queens(&board.get_board_colors()?)?;
+
+    draw_solution(&image, &board, &solution, marker)
+        .save_with_format(output_path, ImageFormat::Png)?;
+    println!("Wrote the solution to {}", output_path);
+    Ok(solution)
+}
+
+fn draw_circle(image: &mut RgbaImage, cx: u32, cy: u32, radius: f32, color: Rgba<u8>) {
+    let (cx, cy) = (cx as f32, cy as f32);
+    fill_shape(
+        image,
+        cx - radius,
+        cy - radius,
+        2.0 * radius,
+        color,
+        |x, y| (x - cx).powi(2) + (y - cy).powi(2) <= radius * radius,
+    );
+}
+
+// a gold crown with a dark outline, centered on (cx, cy) of a `cell_size` cell
+pub(crate) fn draw_crown_marker(image: &mut RgbaImage, cx: u32, cy: u32, cell_size: u32) {
+    // a slightly bigger dark crown behind the gold one is the outline
+    draw_crown(image, cx, cy, cell_size as f32 * 0.65, CROWN_OUTLINE);
+    draw_crown(image, cx, cy, cell_size as f32 * 0.55, CROWN_FILL);
+}
+
+fn draw_crown(image: &mut RgbaImage, cx: u32, cy: u32, size: f32, color: Rgba<u8>) {
+    let (left, top) = (cx as f32 - size / 2.0, cy as f32 - size / 2.0);
+    let polygon: Vec<(f32, f32)> = CROWN_SHAPE
+        .iter()
+        .map(|&(x, y)| (left + x * size, top + y * size))
+        .collect();
+    fill_shape(image, left, top, size, color, |x, y| {
+        inside_polygon(&polygon, x, y)
+    });
+}
+
+// sets every pixel of the `size` square at (left, top) for which `inside`
+// holds, checking the pixel centers
+fn fill_shape(
+    image: &mut RgbaImage,
+    left: f32,
+    top: f32,
+    size: f32,
+    color: Rgba<u8>,
+    inside: impl Fn(f32, f32) -> bool,
+) {
+    let (width, height) = image.dimensions();
+    let x_range = (left.max(0.0) as u32)..((left + size).ceil().max(0.0) as u32).min(width);
+    let y_range = (top.max(0.0) as u32)..((top + size).ceil().max(0.0) as u32).min(height);
+    for y in y_range {
+        for x in x_range.clone() {
+            if inside(x as f32 + 0.5, y as f32 + 0.5) {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+// even-odd rule, count the edges a ray to the right crosses
+fn inside_polygon(polygon: &[(f32, f32)], x: f32, y: f32) -> bool {
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for &current in polygon {
+        let ((x1, y1), (x2, y2)) = (previous, current);
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
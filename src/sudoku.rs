@@ -0,0 +1,406 @@
+use scraper::{Html, Selector};
+use thirtyfour::By;
+use thirtyfour::prelude::*;
+
+use crate::open_browser::{keep_browser_open, start_game};
+
+// Mini Sudoku is a 6x6 sudoku, the boxes are 2 rows by 3 columns
+pub const SUDOKU_SIZE: usize = 6;
+const BOX_ROWS: usize = 2;
+const BOX_COLS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SudokuReason {
+    // the only digit left for the cell
+    NakedSingle,
+    // the only cell left for the digit in its row
+    HiddenSingleRow,
+    // the only cell left for the digit in its column
+    HiddenSingleColumn,
+    // the only cell left for the digit in its box
+    HiddenSingleBox,
+    // no logical step left, tried the digit and it worked out
+    Guess,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SudokuStep {
+    pub row: usize,
+    pub col: usize,
+    pub digit: u8,
+    pub reason: SudokuReason,
+}
+
+#[derive(Debug, Clone)]
+pub struct SudokuSolution {
+    pub grid: Vec<Vec<u8>>,
+    // how each empty cell was filled, in order
+    pub steps: Vec<SudokuStep>,
+}
+
+pub async fn start_sudoku_browser() -> WebDriverResult<(WebDriver, String)> {
+    start_game(
+        "https://www.linkedin.com/games/view/mini-sudoku/desktop",
+        By::Css("div.sudoku-grid"),
+        "div.sudoku-cell",
+    )
+    .await
+}
+
+pub fn parse_sudoku_board(html_content: &str) -> Result<Vec<Vec<Option<u8>>>, String> {
+    println!(
+        "Parsing Sudoku HTML content of length: {}",
+        html_content.len()
+    );
+    let document = Html::parse_document(html_content);
+
+    let cell_selector =
+        Selector::parse("div.sudoku-grid div.sudoku-cell").expect("Failed to parse cell selector");
+    let content_selector =
+        Selector::parse(".sudoku-cell-content").expect("Failed to parse content selector");
+
+    let mut board = vec![vec![None; SUDOKU_SIZE]; SUDOKU_SIZE];
+    let mut cells_found = 0;
+    for (i, cell) in document.select(&cell_selector).enumerate() {
+        let idx = cell
+            .value()
+            .attr("data-cell-idx")
+            .and_then(|idx| idx.trim().parse::<usize>().ok())
+            .unwrap_or(i);
+        if idx >= SUDOKU_SIZE * SUDOKU_SIZE {
+            return Err(format!("Cell index {} out of range", idx));
+        }
+
+        // only the prefilled cells are givens, the rest may hold the
+        // player's own (possibly wrong) digits
+        let prefilled = cell
+            .value()
+            .classes()
+            .any(|class| class == "sudoku-cell-prefilled");
+        if prefilled {
+            let digit = cell
+                .select(&content_selector)
+                .next()
+                .map(|content| content.text().collect::<String>())
+                .unwrap_or_else(|| cell.text().collect::<String>());
+            let digit = digit
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| format!("Prefilled cell {} has no digit: '{}'", idx, digit.trim()))?;
+            board[idx / SUDOKU_SIZE][idx % SUDOKU_SIZE] = Some(digit);
+        }
+        cells_found += 1;
+    }
+
+    if cells_found != SUDOKU_SIZE * SUDOKU_SIZE {
+        return Err(format!(
+            "Found {} cells out of {} total cells",
+            cells_found,
+            SUDOKU_SIZE * SUDOKU_SIZE
+        ));
+    }
+    println!("Final sudoku board:");
+    for row in &board {
+        println!("{:?}", row);
+    }
+
+    Ok(board)
+}
+
+// candidates are kept as a bitmask, bit d is set when digit d still fits
+type Candidates = Vec<Vec<u8>>;
+
+const ALL_DIGITS: u8 = 0b111_1110;
+
+pub fn solve_sudoku(board: &[Vec<Option<u8>>]) -> Result<SudokuSolution, String> {
+    let mut grid = vec![vec![0u8; SUDOKU_SIZE]; SUDOKU_SIZE];
+    let mut candidates: Candidates = vec![vec![ALL_DIGITS; SUDOKU_SIZE]; SUDOKU_SIZE];
+    for row in 0..SUDOKU_SIZE {
+        for col in 0..SUDOKU_SIZE {
+            let Some(digit) = board[row][col] else {
+                continue;
+            };
+            if !(1..=SUDOKU_SIZE as u8).contains(&digit) || candidates[row][col] & (1 << digit) == 0
+            {
+                return Err(format!(
+                    "Prefilled {} at row {}, column {} breaks the rules",
+                    digit,
+                    row + 1,
+                    col + 1
+                ));
+            }
+            place(&mut grid, &mut candidates, row, col, digit);
+        }
+    }
+
+    let mut steps = Vec::new();
+    if solve_logically(&mut grid, &mut candidates, &mut steps) {
+        return Ok(SudokuSolution { grid, steps });
+    }
+    Err("No solution found".to_string())
+}
+
+fn place(grid: &mut [Vec<u8>], candidates: &mut Candidates, row: usize, col: usize, digit: u8) {
+    grid[row][col] = digit;
+    candidates[row][col] = 0;
+    let (box_row, box_col) = (row / BOX_ROWS * BOX_ROWS, col / BOX_COLS * BOX_COLS);
+    for i in 0..SUDOKU_SIZE {
+        candidates[row][i] &= !(1 << digit);
+        candidates[i][col] &= !(1 << digit);
+        candidates[box_row + i / BOX_COLS][box_col + i % BOX_COLS] &= !(1 << digit);
+    }
+}
+
+// the cells of every row, column and box, tagged with the reason a hidden
+// single in that unit would get
+fn units() -> Vec<(SudokuReason, Vec<(usize, usize)>)> {
+    let mut units = Vec::with_capacity(3 * SUDOKU_SIZE);
+    for i in 0..SUDOKU_SIZE {
+        units.push((
+            SudokuReason::HiddenSingleRow,
+            (0..SUDOKU_SIZE).map(|j| (i, j)).collect(),
+        ));
+        units.push((
+            SudokuReason::HiddenSingleColumn,
+            (0..SUDOKU_SIZE).map(|j| (j, i)).collect(),
+        ));
+        let (box_row, box_col) = (i / 2 * BOX_ROWS, i % 2 * BOX_COLS);
+        units.push((
+            SudokuReason::HiddenSingleBox,
+            (0..SUDOKU_SIZE)
+                .map(|j| (box_row + j / BOX_COLS, box_col + j % BOX_COLS))
+                .collect(),
+        ));
+    }
+    units
+}
+
+// applies naked and hidden singles until nothing changes, then guesses on the
+// cell with the fewest candidates. Returns false on a contradiction.
+fn solve_logically(
+    grid: &mut Vec<Vec<u8>>,
+    candidates: &mut Candidates,
+    steps: &mut Vec<SudokuStep>,
+) -> bool {
+    let units = units();
+    loop {
+        let mut progress = false;
+
+        for row in 0..SUDOKU_SIZE {
+            for col in 0..SUDOKU_SIZE {
+                if grid[row][col] != 0 {
+                    continue;
+                }
+                let mask = candidates[row][col];
+                if mask == 0 {
+                    return false;
+                }
+                if mask.count_ones() == 1 {
+                    let digit = mask.trailing_zeros() as u8;
+                    place(grid, candidates, row, col, digit);
+                    steps.push(SudokuStep {
+                        row,
+                        col,
+                        digit,
+                        reason: SudokuReason::NakedSingle,
+                    });
+                    progress = true;
+                }
+            }
+        }
+
+        for (reason, cells) in &units {
+            for digit in 1..=SUDOKU_SIZE as u8 {
+                if cells.iter().any(|&(r, c)| grid[r][c] == digit) {
+                    continue;
+                }
+                let spots: Vec<_> = cells
+                    .iter()
+                    .filter(|&&(r, c)| candidates[r][c] & (1 << digit) != 0)
+                    .collect();
+                match spots[..] {
+                    [] => return false,
+                    [&(row, col)] => {
+                        place(grid, candidates, row, col, digit);
+                        steps.push(SudokuStep {
+                            row,
+                            col,
+                            digit,
+                            reason: *reason,
+                        });
+                        progress = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if !progress {
+            break;
+        }
+    }
+
+    // no logical step left, guess on the most constrained cell
+    let empty = (0..SUDOKU_SIZE)
+        .flat_map(|row| (0..SUDOKU_SIZE).map(move |col| (row, col)))
+        .filter(|&(row, col)| grid[row][col] == 0)
+        .min_by_key(|&(row, col)| candidates[row][col].count_ones());
+    let Some((row, col)) = empty else {
+        return true;
+    };
+
+    for digit in 1..=SUDOKU_SIZE as u8 {
+        if candidates[row][col] & (1 << digit) == 0 {
+            continue;
+        }
+        let mut next_grid = grid.clone();
+        let mut next_candidates = candidates.clone();
+        let mut next_steps = steps.clone();
+        place(&mut next_grid, &mut next_candidates, row, col, digit);
+        next_steps.push(SudokuStep {
+            row,
+            col,
+            digit,
+            reason: SudokuReason::Guess,
+        });
+        if solve_logically(&mut next_grid, &mut next_candidates, &mut next_steps) {
+            *grid = next_grid;
+            *candidates = next_candidates;
+            *steps = next_steps;
+            return true;
+        }
+    }
+    false
+}
+
+pub async fn input_sudoku_solution(
+    driver: &WebDriver,
+    board: &[Vec<Option<u8>>],
+    solution: &SudokuSolution,
+) -> WebDriverResult<()> {
+    let grid = driver.find(By::Css("div.sudoku-grid")).await?;
+
+    // fill the cells in the order the solver found them, selecting a cell and
+    // typing the digit is the same as using the number pad
+    for step in &solution.steps {
+        if board[step.row][step.col].is_some() {
+            continue;
+        }
+        let idx = step.row * SUDOKU_SIZE + step.col;
+        let cell = grid
+            .find(By::Css(format!("[data-cell-idx=\"{}\"]", idx).as_str()))
+            .await?;
+        cell.click().await?;
+        cell.send_keys(step.digit.to_string()).await?;
+    }
+    keep_browser_open().await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = include_str!("../sudoku_board.html");
+
+    // every row, column and box holds 1 to 6 once
+    fn assert_valid_grid(grid: &[Vec<u8>]) {
+        for (_, cells) in units() {
+            let mut digits: Vec<u8> = cells.iter().map(|&(r, c)| grid[r][c]).collect();
+            digits.sort();
+            assert_eq!(digits, [1, 2, 3, 4, 5, 6], "{:?}", cells);
+        }
+    }
+
+    #[test]
+    fn parses_only_the_prefilled_digits() {
+        let board = parse_sudoku_board(BOARD).unwrap();
+        assert_eq!(board.iter().flatten().flatten().count(), 18);
+        assert_eq!(board[0][0], Some(1));
+        assert_eq!(board[5][5], Some(2));
+        // the player's 5 in the last cell of the second row isn't a given
+        assert_eq!(board[1][5], None);
+    }
+
+    #[test]
+    fn solves_the_saved_board() {
+        let board = parse_sudoku_board(BOARD).unwrap();
+        let solution = solve_sudoku(&board).unwrap();
+        assert_valid_grid(&solution.grid);
+        for (row, digits) in board.iter().enumerate() {
+            for (col, given) in digits.iter().enumerate() {
+                if let Some(digit) = given {
+                    assert_eq!(solution.grid[row][col], *digit);
+                }
+            }
+        }
+        // one step per empty cell
+        assert_eq!(solution.steps.len(), 36 - 18);
+    }
+
+    #[test]
+    fn every_step_has_the_reason_it_was_taken_for() {
+        let board = parse_sudoku_board(BOARD).unwrap();
+        let solution = solve_sudoku(&board).unwrap();
+        let mut grid: Vec<Vec<u8>> = board
+            .iter()
+            .map(|row| row.iter().map(|digit| digit.unwrap_or(0)).collect())
+            .collect();
+        // the digits that still fit (row, col) on `grid`
+        let fits = |grid: &[Vec<u8>], row: usize, col: usize, digit: u8| {
+            grid[row][col] == 0
+                && units()
+                    .iter()
+                    .filter(|(_, cells)| cells.contains(&(row, col)))
+                    .all(|(_, cells)| cells.iter().all(|&(r, c)| grid[r][c] != digit))
+        };
+
+        for step in &solution.steps {
+            let (row, col, digit) = (step.row, step.col, step.digit);
+            assert!(fits(&grid, row, col, digit), "{:?}", step);
+            let unit = |reason| {
+                units()
+                    .into_iter()
+                    .find(|(r, cells)| *r == reason && cells.contains(&(row, col)))
+                    .unwrap()
+                    .1
+            };
+            let only = |cells: Vec<(usize, usize)>| {
+                cells
+                    .iter()
+                    .filter(|&&(r, c)| fits(&grid, r, c, digit))
+                    .count()
+                    == 1
+            };
+            match step.reason {
+                SudokuReason::NakedSingle => {
+                    let left = (1..=6).filter(|&d| fits(&grid, row, col, d)).count();
+                    assert_eq!(left, 1, "{:?}", step);
+                }
+                SudokuReason::Guess => panic!("the saved board needs no guess: {:?}", step),
+                reason => assert!(only(unit(reason)), "{:?}", step),
+            }
+            grid[row][col] = digit;
+        }
+        let reasons: Vec<SudokuReason> = solution.steps.iter().map(|step| step.reason).collect();
+        assert!(reasons.contains(&SudokuReason::NakedSingle));
+        assert!(
+            reasons
+                .iter()
+                .any(|reason| *reason != SudokuReason::NakedSingle)
+        );
+    }
+
+    #[test]
+    fn contradictory_givens_are_an_error() {
+        let mut board = parse_sudoku_board(BOARD).unwrap();
+        // a second 1 in the first row
+        board[0][2] = Some(1);
+        assert!(solve_sudoku(&board).is_err());
+
+        let mut board = parse_sudoku_board(BOARD).unwrap();
+        board[0][1] = Some(7);
+        assert!(solve_sudoku(&board).is_err());
+    }
+}
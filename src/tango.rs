@@ -0,0 +1,445 @@
+use scraper::{ElementRef, Html, Selector};
+use thirtyfour::By;
+use thirtyfour::prelude::*;
+
+use crate::html_parser::style_dimension;
+use crate::open_browser::{keep_browser_open, start_game};
+
+// Tango is a binary grid: every cell is a sun or a moon, each row and column
+// has as many suns as moons, no three of the same symbol next to each other,
+// and the `=`/`×` signs between two cells mean same/opposite symbol.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TangoSymbol {
+    Sun,
+    Moon,
+}
+
+impl TangoSymbol {
+    fn opposite(self) -> Self {
+        match self {
+            TangoSymbol::Sun => TangoSymbol::Moon,
+            TangoSymbol::Moon => TangoSymbol::Sun,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TangoConstraint {
+    // `=`
+    Equal,
+    // `×`
+    Opposite,
+}
+
+#[derive(Debug, Clone)]
+pub struct TangoBoard {
+    pub size: usize,
+    // the prefilled (locked) cells
+    pub cells: Vec<Vec<Option<TangoSymbol>>>,
+    // what the player already put in the other cells, clicking has to start
+    // from there
+    pub placed: Vec<Vec<Option<TangoSymbol>>>,
+    // constraint between (row, col) and (row, col + 1)
+    pub right: Vec<Vec<Option<TangoConstraint>>>,
+    // constraint between (row, col) and (row + 1, col)
+    pub down: Vec<Vec<Option<TangoConstraint>>>,
+}
+
+pub async fn start_tango_browser() -> WebDriverResult<(WebDriver, String)> {
+    start_game(
+        "https://www.linkedin.com/games/view/tango/desktop",
+        By::Css("div.lotka-grid"),
+        "div.lotka-cell",
+    )
+    .await
+}
+
+pub fn parse_tango_board(html_content: &str) -> Result<TangoBoard, String> {
+    println!(
+        "Parsing Tango HTML content of length: {}",
+        html_content.len()
+    );
+    let document = Html::parse_document(html_content);
+
+    let grid_selector = Selector::parse("div.lotka-grid").expect("Failed to parse grid selector");
+    let grid = document
+        .select(&grid_selector)
+        .next()
+        .ok_or("Could not find lotka-grid element")?;
+
+    // same `--rows: n; --cols: n` style as the queens grid
+    let style = grid.value().attr("style").unwrap_or_default();
+    let size = style_dimension(style, "--rows")
+        .or_else(|| style_dimension(style, "--cols"))
+        .ok_or("Could not find the grid dimensions in the style")?;
+    if size == 0 || size % 2 != 0 {
+        return Err(format!("Tango grid size must be even, got {}", size));
+    }
+    println!("Tango board dimensions: {}x{}", size, size);
+
+    let cell_selector = Selector::parse("div.lotka-cell").expect("Failed to parse cell selector");
+    let right_edge_selector =
+        Selector::parse(".lotka-cell-edge--right").expect("Failed to parse edge selector");
+    let down_edge_selector =
+        Selector::parse(".lotka-cell-edge--down").expect("Failed to parse edge selector");
+
+    let mut board = TangoBoard {
+        size,
+        cells: vec![vec![None; size]; size],
+        placed: vec![vec![None; size]; size],
+        right: vec![vec![None; size]; size],
+        down: vec![vec![None; size]; size],
+    };
+
+    for (i, cell) in grid.select(&cell_selector).enumerate() {
+        let idx = cell
+            .value()
+            .attr("data-cell-idx")
+            .and_then(|idx| idx.trim().parse::<usize>().ok())
+            .unwrap_or(i);
+        if idx >= size * size {
+            return Err(format!("Cell index {} out of range", idx));
+        }
+        let (row, col) = (idx / size, idx % size);
+
+        // only the locked cells are givens, anything else on the page was
+        // placed by the player and is kept apart so the solver ignores it
+        let locked = cell
+            .value()
+            .classes()
+            .any(|class| class == "lotka-cell--locked");
+        if locked {
+            board.cells[row][col] = symbol_in(cell);
+        } else {
+            board.placed[row][col] = symbol_in(cell);
+        }
+        board.right[row][col] = cell
+            .select(&right_edge_selector)
+            .next()
+            .and_then(constraint_in);
+        board.down[row][col] = cell
+            .select(&down_edge_selector)
+            .next()
+            .and_then(constraint_in);
+    }
+
+    Ok(board)
+}
+
+// the icons carry a `data-testid` (cell-zero is the sun, cell-one the moon),
+// older markup only had the english aria-label/title
+fn symbol_in(element: ElementRef) -> Option<TangoSymbol> {
+    for node in element.descendants().filter_map(ElementRef::wrap) {
+        let value = node.value();
+        let hints = [
+            value.attr("data-testid"),
+            value.attr("aria-label"),
+            value.attr("title"),
+        ];
+        for hint in hints.into_iter().flatten() {
+            let hint = hint.to_lowercase();
+            if hint.contains("cell-zero") || hint.contains("sun") {
+                return Some(TangoSymbol::Sun);
+            }
+            if hint.contains("cell-one") || hint.contains("moon") {
+                return Some(TangoSymbol::Moon);
+            }
+        }
+    }
+    None
+}
+
+fn constraint_in(element: ElementRef) -> Option<TangoConstraint> {
+    for node in element.descendants().filter_map(ElementRef::wrap) {
+        let value = node.value();
+        let hints = [
+            value.attr("data-testid"),
+            value.attr("aria-label"),
+            value.attr("title"),
+        ];
+        for hint in hints.into_iter().flatten() {
+            let hint = hint.to_lowercase();
+            if hint.contains("equal") {
+                return Some(TangoConstraint::Equal);
+            }
+            if hint.contains("cross") || hint.contains("opposite") {
+                return Some(TangoConstraint::Opposite);
+            }
+        }
+    }
+    None
+}
+
+pub fn solve_tango(board: &TangoBoard) -> Result<Vec<Vec<TangoSymbol>>, String> {
+    let n = board.size;
+    let mut grid = board.cells.clone();
+    // the givens have to be consistent before we start filling around them
+    for row in 0..n {
+        for col in 0..n {
+            let Some(symbol) = grid[row][col] else {
+                continue;
+            };
+            if !is_valid_tango(board, &grid, row, col, symbol) {
+                return Err(format!(
+                    "Prefilled cell at row {}, column {} breaks the rules",
+                    row + 1,
+                    col + 1
+                ));
+            }
+        }
+    }
+
+    // same idea as the queens solver, plain backtracking over the cells in
+    // row-major order, skipping the prefilled ones
+    if backtrack_tango(board, &mut grid, 0) {
+        return Ok(grid
+            .into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.unwrap()).collect())
+            .collect());
+    }
+    Err("No solution found".to_string())
+}
+
+fn backtrack_tango(board: &TangoBoard, grid: &mut [Vec<Option<TangoSymbol>>], idx: usize) -> bool {
+    let n = board.size;
+    if idx == n * n {
+        return true;
+    }
+    let (row, col) = (idx / n, idx % n);
+    if grid[row][col].is_some() {
+        return backtrack_tango(board, grid, idx + 1);
+    }
+
+    for symbol in [TangoSymbol::Sun, TangoSymbol::Moon] {
+        if is_valid_tango(board, grid, row, col, symbol) {
+            // change state
+            grid[row][col] = Some(symbol);
+            // backtrack
+            if backtrack_tango(board, grid, idx + 1) {
+                return true;
+            }
+            // undo change
+            grid[row][col] = None;
+        }
+    }
+    false
+}
+
+fn is_valid_tango(
+    board: &TangoBoard,
+    grid: &[Vec<Option<TangoSymbol>>],
+    row: usize,
+    col: usize,
+    symbol: TangoSymbol,
+) -> bool {
+    let n = board.size;
+    let at = |r: usize, c: usize| {
+        if r == row && c == col {
+            Some(symbol)
+        } else {
+            grid[r][c]
+        }
+    };
+
+    // at most n/2 of each symbol in the row and the column
+    let row_count = (0..n).filter(|&c| at(row, c) == Some(symbol)).count();
+    let col_count = (0..n).filter(|&r| at(r, col) == Some(symbol)).count();
+    if row_count > n / 2 || col_count > n / 2 {
+        return false;
+    }
+
+    // no three in a row, check every window of three that contains the cell
+    for start in col.saturating_sub(2)..=col {
+        if start + 2 < n && (start..start + 3).all(|c| at(row, c) == Some(symbol)) {
+            return false;
+        }
+    }
+    for start in row.saturating_sub(2)..=row {
+        if start + 2 < n && (start..start + 3).all(|r| at(r, col) == Some(symbol)) {
+            return false;
+        }
+    }
+
+    // `=` and `×` with the four neighbours
+    let mut neighbours = Vec::with_capacity(4);
+    if col + 1 < n {
+        neighbours.push((row, col + 1, board.right[row][col]));
+    }
+    if col > 0 {
+        neighbours.push((row, col - 1, board.right[row][col - 1]));
+    }
+    if row + 1 < n {
+        neighbours.push((row + 1, col, board.down[row][col]));
+    }
+    if row > 0 {
+        neighbours.push((row - 1, col, board.down[row - 1][col]));
+    }
+    for (r, c, constraint) in neighbours {
+        let (Some(constraint), Some(other)) = (constraint, at(r, c)) else {
+            continue;
+        };
+        let expected = match constraint {
+            TangoConstraint::Equal => symbol,
+            TangoConstraint::Opposite => symbol.opposite(),
+        };
+        if other != expected {
+            return false;
+        }
+    }
+
+    true
+}
+
+// a cell cycles empty -> sun -> moon -> empty on each click
+fn clicks_between(current: Option<TangoSymbol>, target: TangoSymbol) -> usize {
+    let step = |symbol: Option<TangoSymbol>| match symbol {
+        None => 0,
+        Some(TangoSymbol::Sun) => 1,
+        Some(TangoSymbol::Moon) => 2,
+    };
+    (step(Some(target)) + 3 - step(current)) % 3
+}
+
+pub async fn click_tango_solution(
+    driver: &WebDriver,
+    board: &TangoBoard,
+    solution: &[Vec<TangoSymbol>],
+) -> WebDriverResult<()> {
+    let grid = driver.find(By::Css("div.lotka-grid")).await?;
+
+    for (row, symbols) in solution.iter().enumerate() {
+        for (col, &symbol) in symbols.iter().enumerate() {
+            // locked cells can't be changed
+            if board.cells[row][col].is_some() {
+                continue;
+            }
+            let idx = row * board.size + col;
+            let cell = grid
+                .find(By::Css(format!("[data-cell-idx=\"{}\"]", idx).as_str()))
+                .await?;
+
+            for _ in 0..clicks_between(board.placed[row][col], symbol) {
+                cell.click().await?;
+            }
+        }
+    }
+    keep_browser_open().await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 4x4 grid, `cells` holds (idx, locked, testid) for the non empty cells
+    // and `edges` (idx, "right" or "down", testid) for the signs
+    fn grid_html(cells: &[(usize, bool, &str)], edges: &[(usize, &str, &str)]) -> String {
+        let mut html = String::from(r#"<div class="lotka-grid" style="--rows: 4; --cols: 4;">"#);
+        for idx in 0..16 {
+            let filled = cells.iter().find(|cell| cell.0 == idx);
+            let locked = if filled.is_some_and(|cell| cell.1) {
+                " lotka-cell--locked"
+            } else {
+                ""
+            };
+            html += &format!(
+                r#"<div class="lotka-cell{}" data-cell-idx="{}">"#,
+                locked, idx
+            );
+            if let Some((_, _, testid)) = filled {
+                html += &format!(r#"<svg data-testid="{}"></svg>"#, testid);
+            }
+            for (_, side, testid) in edges.iter().filter(|edge| edge.0 == idx) {
+                html += &format!(
+                    r#"<div class="lotka-cell-edge lotka-cell-edge--{}"><svg data-testid="{}"></svg></div>"#,
+                    side, testid
+                );
+            }
+            html += "</div>";
+        }
+        html + "</div>"
+    }
+
+    #[test]
+    fn symbols_placed_by_the_player_are_not_givens() {
+        let html = grid_html(
+            &[
+                (0, true, "cell-zero"),
+                (1, false, "cell-one"),
+                (5, false, "cell-zero"),
+            ],
+            &[],
+        );
+        let board = parse_tango_board(&html).unwrap();
+        assert_eq!(board.cells[0][0], Some(TangoSymbol::Sun));
+        assert_eq!(board.cells[0][1], None);
+        assert_eq!(board.placed[0][0], None);
+        assert_eq!(board.placed[0][1], Some(TangoSymbol::Moon));
+        assert_eq!(board.placed[1][1], Some(TangoSymbol::Sun));
+
+        let solution = solve_tango(&board).unwrap();
+        assert_eq!(solution[0][0], TangoSymbol::Sun);
+    }
+
+    #[test]
+    fn signs_between_cells_are_parsed_and_respected() {
+        use TangoConstraint::{Equal, Opposite};
+        let edges = [
+            (0, "right", "edge-cross"),
+            (0, "down", "edge-equal"),
+            (5, "right", "edge-equal"),
+            (10, "down", "edge-cross"),
+        ];
+        let board = parse_tango_board(&grid_html(&[], &edges)).unwrap();
+        let mut right = vec![vec![None; 4]; 4];
+        let mut down = vec![vec![None; 4]; 4];
+        right[0][0] = Some(Opposite);
+        down[0][0] = Some(Equal);
+        right[1][1] = Some(Equal);
+        down[2][2] = Some(Opposite);
+        assert_eq!(board.right, right);
+        assert_eq!(board.down, down);
+
+        let solution = solve_tango(&board).unwrap();
+        for row in 0..4 {
+            for col in 0..4 {
+                let pairs = [
+                    (board.right[row][col], row, col + 1),
+                    (board.down[row][col], row + 1, col),
+                ];
+                for (constraint, r, c) in pairs {
+                    let Some(constraint) = constraint else {
+                        continue;
+                    };
+                    let same = solution[row][col] == solution[r][c];
+                    assert_eq!(
+                        same,
+                        constraint == Equal,
+                        "{} {} {:?}",
+                        row,
+                        col,
+                        constraint
+                    );
+                }
+            }
+        }
+
+        // without the signs the solver settles on another grid, so they
+        // decided the answer
+        let unsigned = parse_tango_board(&grid_html(&[], &[])).unwrap();
+        assert_ne!(solve_tango(&unsigned).unwrap(), solution);
+    }
+
+    #[test]
+    fn clicks_go_from_the_current_symbol_to_the_target() {
+        use TangoSymbol::{Moon, Sun};
+        assert_eq!(clicks_between(None, Sun), 1);
+        assert_eq!(clicks_between(None, Moon), 2);
+        assert_eq!(clicks_between(Some(Sun), Sun), 0);
+        assert_eq!(clicks_between(Some(Sun), Moon), 1);
+        assert_eq!(clicks_between(Some(Moon), Sun), 2);
+        assert_eq!(clicks_between(Some(Moon), Moon), 0);
+    }
+}
@@ -0,0 +1,334 @@
+use scraper::{Html, Selector};
+use thirtyfour::By;
+use thirtyfour::prelude::*;
+
+use crate::html_parser::style_dimension;
+use crate::open_browser::{keep_browser_open, start_game};
+
+// Zip is a single path that visits every cell exactly once, starting on 1,
+// going through the numbered checkpoints in order and ending on the last one,
+// without crossing the walls between cells.
+
+#[derive(Debug, Clone)]
+pub struct ZipBoard {
+    pub rows: usize,
+    pub cols: usize,
+    pub numbers: Vec<Vec<Option<u32>>>,
+    // wall between (row, col) and (row, col + 1)
+    pub right_walls: Vec<Vec<bool>>,
+    // wall between (row, col) and (row + 1, col)
+    pub down_walls: Vec<Vec<bool>>,
+}
+
+impl ZipBoard {
+    fn neighbours(&self, idx: usize) -> Vec<usize> {
+        let (row, col) = (idx / self.cols, idx % self.cols);
+        let mut result = Vec::with_capacity(4);
+        if col + 1 < self.cols && !self.right_walls[row][col] {
+            result.push(idx + 1);
+        }
+        if col > 0 && !self.right_walls[row][col - 1] {
+            result.push(idx - 1);
+        }
+        if row + 1 < self.rows && !self.down_walls[row][col] {
+            result.push(idx + self.cols);
+        }
+        if row > 0 && !self.down_walls[row - 1][col] {
+            result.push(idx - self.cols);
+        }
+        result
+    }
+}
+
+pub async fn start_zip_browser() -> WebDriverResult<(WebDriver, String)> {
+    start_game(
+        "https://www.linkedin.com/games/view/zip/desktop",
+        By::Css("div.trail-grid"),
+        "div.trail-cell",
+    )
+    .await
+}
+
+pub fn parse_zip_board(html_content: &str) -> Result<ZipBoard, String> {
+    println!("Parsing Zip HTML content of length: {}", html_content.len());
+    let document = Html::parse_document(html_content);
+
+    let grid_selector = Selector::parse("div.trail-grid").expect("Failed to parse grid selector");
+    let grid = document
+        .select(&grid_selector)
+        .next()
+        .ok_or("Could not find trail-grid element")?;
+
+    let style = grid.value().attr("style").unwrap_or_default();
+    let rows = style_dimension(style, "--rows").ok_or("Could not find --rows in style")?;
+    let cols = style_dimension(style, "--cols").ok_or("Could not find --cols in style")?;
+    println!("Zip board dimensions: {}x{}", rows, cols);
+
+    let cell_selector = Selector::parse("div.trail-cell").expect("Failed to parse cell selector");
+    let content_selector =
+        Selector::parse(".trail-cell-content").expect("Failed to parse content selector");
+    let wall_selector =
+        Selector::parse("[class*=\"trail-cell-wall--\"]").expect("Failed to parse wall selector");
+
+    let mut board = ZipBoard {
+        rows,
+        cols,
+        numbers: vec![vec![None; cols]; rows],
+        right_walls: vec![vec![false; cols]; rows],
+        down_walls: vec![vec![false; cols]; rows],
+    };
+
+    for (i, cell) in grid.select(&cell_selector).enumerate() {
+        let idx = cell
+            .value()
+            .attr("data-cell-idx")
+            .and_then(|idx| idx.trim().parse::<usize>().ok())
+            .unwrap_or(i);
+        if idx >= rows * cols {
+            return Err(format!("Cell index {} out of range", idx));
+        }
+        let (row, col) = (idx / cols, idx % cols);
+
+        board.numbers[row][col] = cell
+            .select(&content_selector)
+            .next()
+            .and_then(|content| content.text().collect::<String>().trim().parse().ok());
+
+        // a wall is drawn on one side of one of the two cells, store it on the
+        // cell above / to the left
+        for wall in cell.select(&wall_selector) {
+            for class in wall.value().classes() {
+                match class.strip_prefix("trail-cell-wall--") {
+                    Some("right") if col + 1 < cols => board.right_walls[row][col] = true,
+                    Some("left") if col > 0 => board.right_walls[row][col - 1] = true,
+                    Some("down") if row + 1 < rows => board.down_walls[row][col] = true,
+                    Some("up") if row > 0 => board.down_walls[row - 1][col] = true,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(board)
+}
+
+// returns the path as `data-cell-idx` values, in the order they're visited
+pub fn solve_zip(board: &ZipBoard) -> Result<Vec<usize>, String> {
+    let total = board.rows * board.cols;
+    let numbers: Vec<Option<u32>> = board.numbers.iter().flatten().copied().collect();
+
+    let mut checkpoints: Vec<(u32, usize)> = numbers
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, number)| number.map(|number| (number, idx)))
+        .collect();
+    checkpoints.sort();
+    if checkpoints.is_empty() {
+        return Err("Board has no numbered cells".to_string());
+    }
+    for (i, &(number, _)) in checkpoints.iter().enumerate() {
+        if number != i as u32 + 1 {
+            return Err(format!(
+                "Checkpoints are not numbered 1..n, found {}",
+                number
+            ));
+        }
+    }
+
+    let start = checkpoints[0].1;
+    let mut visited = vec![false; total];
+    let mut path = Vec::with_capacity(total);
+    visited[start] = true;
+    path.push(start);
+
+    if backtrack_zip(
+        board,
+        &numbers,
+        checkpoints.len() as u32,
+        &mut visited,
+        &mut path,
+        2,
+    ) {
+        return Ok(path);
+    }
+    Err("No solution found".to_string())
+}
+
+fn backtrack_zip(
+    board: &ZipBoard,
+    numbers: &[Option<u32>],
+    last_checkpoint: u32,
+    visited: &mut [bool],
+    path: &mut Vec<usize>,
+    next_checkpoint: u32,
+) -> bool {
+    let current = *path.last().unwrap();
+    if path.len() == visited.len() {
+        // the path has to end on the last checkpoint
+        return numbers[current] == Some(last_checkpoint);
+    }
+    if numbers[current] == Some(last_checkpoint) {
+        return false;
+    }
+    // every cell that's left has to stay reachable from where we are. Leaving
+    // the previous cell can only cut the rest in two when its neighbours don't
+    // touch each other around it, the full flood fill is only needed then
+    let split_possible = match path.len() {
+        1 => true,
+        len => !neighbours_touch_around(board, visited, current, path[len - 2]),
+    };
+    if split_possible && !unvisited_connected(board, visited, current) {
+        return false;
+    }
+
+    for next in board.neighbours(current) {
+        if visited[next] {
+            continue;
+        }
+        // checkpoints must be hit in order, never skip ahead to a later one
+        let mut checkpoint = next_checkpoint;
+        match numbers[next] {
+            Some(number) if number == next_checkpoint => checkpoint += 1,
+            Some(_) => continue,
+            None => {}
+        }
+
+        // change state
+        visited[next] = true;
+        path.push(next);
+        // backtrack
+        if backtrack_zip(board, numbers, last_checkpoint, visited, path, checkpoint) {
+            return true;
+        }
+        // undo change
+        path.pop();
+        visited[next] = false;
+    }
+    false
+}
+
+// whether the cells next to `left` that the path can still use (the unvisited
+// ones and `current`) all reach each other inside the 3x3 block around `left`
+fn neighbours_touch_around(
+    board: &ZipBoard,
+    visited: &[bool],
+    current: usize,
+    left: usize,
+) -> bool {
+    let open = |idx: usize| idx != left && (!visited[idx] || idx == current);
+    let (row, col) = (left / board.cols, left % board.cols);
+    let in_block =
+        |idx: usize| (idx / board.cols).abs_diff(row) <= 1 && (idx % board.cols).abs_diff(col) <= 1;
+
+    let targets: Vec<usize> = board
+        .neighbours(left)
+        .into_iter()
+        .filter(|&idx| open(idx))
+        .collect();
+    let Some(&first) = targets.first() else {
+        return true;
+    };
+    let mut seen = vec![first];
+    let mut stack = vec![first];
+    while let Some(idx) = stack.pop() {
+        for next in board.neighbours(idx) {
+            if in_block(next) && open(next) && !seen.contains(&next) {
+                seen.push(next);
+                stack.push(next);
+            }
+        }
+    }
+    targets.iter().all(|idx| seen.contains(idx))
+}
+
+fn unvisited_connected(board: &ZipBoard, visited: &[bool], current: usize) -> bool {
+    let remaining = visited.iter().filter(|&&v| !v).count();
+    let mut seen = vec![false; visited.len()];
+    let mut stack = vec![current];
+    seen[current] = true;
+    let mut reached = 0;
+    while let Some(idx) = stack.pop() {
+        for next in board.neighbours(idx) {
+            if !visited[next] && !seen[next] {
+                seen[next] = true;
+                reached += 1;
+                stack.push(next);
+            }
+        }
+    }
+    reached == remaining
+}
+
+pub async fn click_zip_path(driver: &WebDriver, path: &[usize]) -> WebDriverResult<()> {
+    let grid = driver.find(By::Css("div.trail-grid")).await?;
+
+    // clicking a cell next to the end of the path extends it, so clicking the
+    // cells in order draws the whole path (the first one is already on it)
+    for &idx in path.iter().skip(1) {
+        let cell = grid
+            .find(By::Css(format!("[data-cell-idx=\"{}\"]", idx).as_str()))
+            .await?;
+        cell.click().await?;
+    }
+    keep_browser_open().await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = include_str!("../zip_board.html");
+
+    // a path that visits every cell once, through open sides only, and meets
+    // the checkpoints in order, ending on the last one
+    fn assert_valid_path(board: &ZipBoard, path: &[usize]) {
+        let numbers: Vec<Option<u32>> = board.numbers.iter().flatten().copied().collect();
+        let mut sorted = path.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (0..board.rows * board.cols).collect::<Vec<_>>());
+        for step in path.windows(2) {
+            assert!(board.neighbours(step[0]).contains(&step[1]), "{:?}", step);
+        }
+        let checkpoints: Vec<u32> = path.iter().filter_map(|&idx| numbers[idx]).collect();
+        let expected: Vec<u32> = (1..=checkpoints.len() as u32).collect();
+        assert_eq!(checkpoints, expected);
+        assert_eq!(numbers[*path.last().unwrap()], checkpoints.last().copied());
+    }
+
+    #[test]
+    fn parses_numbers_and_walls() {
+        let board = parse_zip_board(BOARD).unwrap();
+        assert_eq!((board.rows, board.cols), (5, 5));
+        assert_eq!(board.numbers[0][0], Some(1));
+        assert_eq!(board.numbers[0][4], Some(2));
+        assert_eq!(board.numbers[4][4], Some(6));
+        // each wall is stored once, on the cell above / to the left
+        assert!(board.down_walls[0][1]);
+        assert!(board.down_walls[0][3]);
+        assert!(board.down_walls[1][2]);
+        assert!(board.right_walls[3][0]);
+        assert!(board.right_walls[3][1]);
+        assert!(board.right_walls[4][1]);
+        assert_eq!(
+            board.right_walls.iter().flatten().filter(|&&w| w).count(),
+            3
+        );
+        assert_eq!(board.down_walls.iter().flatten().filter(|&&w| w).count(), 3);
+    }
+
+    #[test]
+    fn solves_the_saved_board() {
+        let board = parse_zip_board(BOARD).unwrap();
+        let path = solve_zip(&board).unwrap();
+        assert_valid_path(&board, &path);
+    }
+
+    #[test]
+    fn walls_that_cut_the_board_make_it_unsolvable() {
+        let mut board = parse_zip_board(BOARD).unwrap();
+        board.down_walls[2] = vec![true; board.cols];
+        assert!(solve_zip(&board).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::solution;
+
+use linkedin_queens::{
+    batch_report_csv, batch_report_json, count_queens_solutions, process_directory, process_image,
+    write_batch_report,
+};
+
+// a fresh directory under the system temp dir, per test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("queens-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn directory_of_fixtures_is_solved_in_path_order() {
+    let dir = temp_dir("batch");
+    std::fs::create_dir_all(dir.join("older")).unwrap();
+    std::fs::copy("imagez.png", dir.join("b.png")).unwrap();
+    std::fs::copy("image0.png", dir.join("older/a.png")).unwrap();
+    // not an image, left out
+    std::fs::write(dir.join("notes.txt"), "not a board").unwrap();
+    // named like an image but isn't one, reported as an error
+    std::fs::write(dir.join("a.png"), "not a board").unwrap();
+
+    let results = process_directory(&dir.to_string_lossy()).unwrap();
+    let names: Vec<PathBuf> = results
+        .iter()
+        .map(|result| result.path.strip_prefix(&dir).unwrap().to_path_buf())
+        .collect();
+    assert_eq!(
+        names,
+        [PathBuf::from("a.png"), "b.png".into(), "older/a.png".into()]
+    );
+
+    assert!(results[0].error.is_some());
+    assert_eq!(results[0].solution, None);
+    assert_eq!(results[1].grid_size, Some(9));
+    assert_eq!(results[1].solution.as_deref(), Some(solution("imagez.png")));
+    assert_eq!(results[1].unique, Some(true));
+    assert_eq!(results[2].grid_size, Some(8));
+    assert_eq!(results[2].solution.as_deref(), Some(solution("image0.png")));
+
+    let csv = batch_report_csv(&results);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("path,grid_size,found,unique,solution"));
+    let cells: Vec<String> = solution("imagez.png")
+        .iter()
+        .map(|cell| cell.to_string())
+        .collect();
+    assert!(lines[2].contains(&format!(",9,true,true,{},", cells.join(" "))));
+    assert!(lines[1].contains(",,false,,,"));
+
+    let json = batch_report_json(&results);
+    assert!(json.contains(&format!("\"solution\": {:?}", solution("imagez.png"))));
+    assert!(json.contains("\"grid_size\": null"));
+
+    // the extension picks the format
+    let report = dir.join("report.json");
+    write_batch_report(&results, &report.to_string_lossy()).unwrap();
+    assert_eq!(std::fs::read_to_string(&report).unwrap(), json);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fixtures_have_a_single_solution() {
+    for path in ["image0.png", "imagez.png", "image_04062025.png"] {
+        let board = process_image(path).unwrap();
+        assert_eq!(count_queens_solutions(&board, 2), 1, "{}", path);
+    }
+    // a board where every row is its own region has more than one
+    let rows: Vec<Vec<usize>> = (0..5).map(|row| vec![row; 5]).collect();
+    assert_eq!(count_queens_solutions(&rows, 2), 2);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::{fixtures, open};
+use image::ImageFormat;
+use linkedin_queens::{process_image, process_image_bytes, process_image_reader, queens};
+
+const PATHS: &[&str] = &["image0.png", "image_04062025.png", "imagez.png"];
+
+#[test]
+fn bytes_and_readers_read_like_files() {
+    for (path, solution) in fixtures(PATHS) {
+        let from_file = process_image(path).unwrap();
+        assert_eq!(queens(&from_file).unwrap(), solution, "{}", path);
+        let bytes = std::fs::read(path).unwrap();
+        assert_eq!(process_image_bytes(&bytes).unwrap(), from_file, "{}", path);
+        assert_eq!(
+            process_image_reader(Cursor::new(&bytes)).unwrap(),
+            from_file,
+            "{}",
+            path
+        );
+    }
+}
+
+#[test]
+fn format_comes_from_the_bytes_not_the_name() {
+    for (path, _) in fixtures(PATHS) {
+        let from_file = process_image(path).unwrap();
+        // the same screenshot as a JPEG and as a WebP, nothing says which is which
+        for format in [ImageFormat::Jpeg, ImageFormat::WebP] {
+            let image = open(path).to_rgb8();
+            let mut bytes = Vec::new();
+            image
+                .write_to(&mut Cursor::new(&mut bytes), format)
+                .unwrap();
+            assert_eq!(
+                process_image_bytes(&bytes).unwrap(),
+                from_file,
+                "{} {:?}",
+                path,
+                format
+            );
+        }
+    }
+}
+
+#[test]
+fn bytes_that_arent_an_image_are_an_error() {
+    assert!(process_image_bytes(b"not a screenshot").is_err());
+    assert!(process_image_reader(Cursor::new(Vec::new())).is_err());
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{fixtures, open, solution};
+use linkedin_queens::{BoardImage, Calibration, process_image, queens};
+
+#[test]
+fn profile_from_a_fixture_reads_it_the_same() {
+    let paths = [
+        "image0.png",
+        "imagez.png",
+        "image_04062025.png",
+        "image_with_queens.png",
+    ];
+    for (path, solution) in fixtures(&paths) {
+        let image = open(path);
+        let board = BoardImage::from_screenshot(image.clone()).unwrap();
+        let colors = board.get_board_colors().unwrap();
+
+        // saved and loaded back as text, nothing is lost
+        let calibration = board.calibration("fixture").unwrap();
+        let parsed = Calibration::parse(&calibration.to_text()).unwrap();
+        assert_eq!(parsed, calibration, "{}", path);
+        assert_eq!(parsed.palette.len(), board.grid_size(), "{}", path);
+
+        let calibrated = BoardImage::from_screenshot_with_calibration(image, parsed).unwrap();
+        assert_eq!(calibrated.get_board_colors().unwrap(), colors, "{}", path);
+        assert_eq!(queens(&colors).unwrap(), solution, "{}", path);
+    }
+}
+
+#[test]
+fn broken_profiles_are_rejected() {
+    assert!(Calibration::parse("window_size = 0").is_err());
+    assert!(Calibration::parse("color Chartreuse = 1 2 3").is_err());
+    assert!(Calibration::parse("color Pink = 1 2").is_err());
+    assert!(Calibration::parse("sample_inset = 0.6").is_err());
+}
+
+#[test]
+fn library_reads_with_the_defaults_whatever_the_env_says() {
+    // a saved profile no fixture can be read with
+    let profile = Calibration {
+        name: "too-big-for-the-fixtures".to_string(),
+        min_grid_size: 20,
+        ..Calibration::default()
+    };
+    let saved = profile.save_named().unwrap();
+    let loaded = Calibration::load_named(&profile.name).unwrap();
+    assert!(BoardImage::from_screenshot_with_calibration(open("image0.png"), loaded).is_err());
+
+    // only main.rs picks the profile up from the environment, nothing else in
+    // this test binary reads it
+    unsafe { std::env::set_var("QUEENS_CALIBRATION", &profile.name) };
+    let board = BoardImage::from_screenshot(open("image0.png"));
+    let colors = process_image("image0.png");
+    std::fs::remove_file(&saved).unwrap();
+    // only goes if the profile was the only one there
+    let _ = std::fs::remove_dir(saved.parent().unwrap());
+
+    let colors = colors.unwrap();
+    assert_eq!(board.unwrap().get_board_colors().unwrap(), colors);
+    assert_eq!(queens(&colors).unwrap(), solution("image0.png"));
+}
@@ -0,0 +1,47 @@
+// What the integration tests share: the screenshots in the repo, what they
+// solve to and how a test reads one. Every test file `mod common;`s this and
+// uses only some of it.
+#![allow(dead_code)]
+
+use image::DynamicImage;
+use linkedin_queens::{BoardImage, queens};
+
+// the screenshots in the repo and their solutions
+pub const FIXTURES: &[(&str, &[usize])] = &[
+    ("image0.png", &[2, 12, 17, 30, 35, 47, 53, 56]),
+    ("imagez.png", &[4, 9, 21, 34, 38, 51, 62, 68, 73]),
+    ("image-1.png", &[4, 9, 21, 34, 38, 51, 62, 68, 73]),
+    ("image_04062025.png", &[6, 11, 21, 24, 36, 41, 55, 58]),
+    ("image_with_queens.png", &[2, 14, 20, 31, 37, 40, 51, 57]),
+    ("image_without_queens.png", &[2, 14, 20, 31, 37, 40, 51, 57]),
+];
+
+// the games nobody played yet, without a queen or an X on them
+pub const UNMARKED: &[&str] = &[
+    "image_04062025.png",
+    "image_without_queens.png",
+    "imagez.png",
+];
+
+pub fn solution(path: &str) -> &'static [usize] {
+    FIXTURES
+        .iter()
+        .find(|(fixture, _)| *fixture == path)
+        .unwrap_or_else(|| panic!("{} isn't one of the fixtures", path))
+        .1
+}
+
+// `paths` with their solutions, in that order
+pub fn fixtures(paths: &[&'static str]) -> Vec<(&'static str, &'static [usize])> {
+    paths.iter().map(|&path| (path, solution(path))).collect()
+}
+
+pub fn open(path: &str) -> DynamicImage {
+    image::open(path).unwrap()
+}
+
+// finds the board in `image`, reads it and solves it
+pub fn solve(image: DynamicImage) -> Vec<usize> {
+    let board = BoardImage::from_screenshot(image).unwrap();
+    queens(&board.get_board_colors().unwrap()).unwrap()
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{UNMARKED, fixtures, open, solve};
+use linkedin_queens::{
+    CellColor, RenderOptions, Theme, detect_theme, locate_board, parse_board, process_image_bytes,
+    queens, render_board,
+};
+
+// the 7x7 board saved from the page
+fn saved_board() -> Vec<Vec<CellColor>> {
+    parse_board(include_str!("../html_board1.html")).unwrap()
+}
+
+// same cells grouped together, whatever each group is called
+fn same_regions(a: &[Vec<CellColor>], b: &[Vec<CellColor>]) -> bool {
+    let cells: Vec<(CellColor, CellColor)> = a
+        .iter()
+        .flatten()
+        .copied()
+        .zip(b.iter().flatten().copied())
+        .collect();
+    cells
+        .iter()
+        .all(|x| cells.iter().all(|y| (x.0 == y.0) == (x.1 == y.1)))
+}
+
+#[test]
+fn dark_screenshot_keeps_the_regions_and_the_solution() {
+    let colors = saved_board();
+    let options = RenderOptions {
+        theme: Theme::Dark,
+        ..RenderOptions::default()
+    };
+    let image = render_board(&colors, None, &options);
+    assert_eq!(detect_theme(&image), Theme::Dark);
+
+    let mut bytes = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+    let read = process_image_bytes(&bytes).unwrap();
+    assert!(same_regions(&read, &colors));
+    assert_eq!(queens(&read).unwrap(), queens(&colors).unwrap());
+}
+
+#[test]
+fn fixture_on_a_dark_page_is_read_as_dark_and_solved() {
+    for (path, solution) in fixtures(UNMARKED) {
+        // everything around the board painted the dark page color, with some
+        // room so the page isn't just the edge of the image
+        let image = open(path);
+        let region = locate_board(&image).unwrap();
+        let margin = 30;
+        let (width, height) = (image.width() + 2 * margin, image.height() + 2 * margin);
+        let mut dark = image::RgbaImage::from_pixel(width, height, image::Rgba([27, 31, 35, 255]));
+        let board = image.crop_imm(region.x, region.y, region.width, region.height);
+        image::imageops::replace(
+            &mut dark,
+            &board.to_rgba8(),
+            (region.x + margin) as i64,
+            (region.y + margin) as i64,
+        );
+        let dark = image::DynamicImage::ImageRgba8(dark);
+
+        assert_eq!(detect_theme(&dark), Theme::Dark, "{}", path);
+        assert_eq!(solve(dark), solution, "{}", path);
+    }
+}
@@ -0,0 +1,20 @@
+use image::GenericImageView;
+use linkedin_queens::BoardImage;
+
+#[test]
+fn overlay_is_the_board_with_the_reading_drawn_on_it() {
+    let board = BoardImage::from_screenshot(image::open("image0.png").unwrap()).unwrap();
+    let path = std::env::temp_dir().join("queens_debug_overlay_test.png");
+    board.write_debug_overlay(path.to_str().unwrap()).unwrap();
+
+    let overlay = image::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let cell_size = board.cell_size();
+    assert!(overlay.width() >= 8 * cell_size && overlay.height() >= 8 * cell_size);
+    // the detected lines are drawn in magenta
+    let magenta = overlay
+        .pixels()
+        .filter(|(_, _, pixel)| pixel.0 == [255, 0, 255, 255])
+        .count();
+    assert!(magenta as u32 >= 2 * 9 * 8 * cell_size);
+}
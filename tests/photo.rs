@@ -0,0 +1,37 @@
+mod common;
+
+use common::{UNMARKED, fixtures, open};
+use image::{DynamicImage, Rgba, RgbaImage};
+use linkedin_queens::{BoardImage, queens, warp_to_square};
+
+// `path` on a page with room around it, then seen at an angle: the square
+// `corners` of the page are pulled to a quadrilateral
+fn photographed(path: &str, corners: [(f32, f32); 4]) -> DynamicImage {
+    let board = open(path);
+    let margin = 150;
+    let side = board.width().max(board.height()) + 2 * margin;
+    let mut page = RgbaImage::from_pixel(side, side, Rgba([243, 242, 239, 255]));
+    image::imageops::replace(&mut page, &board.to_rgba8(), margin as i64, margin as i64);
+    let page = DynamicImage::ImageRgba8(page);
+    // the page corners land on `corners`, a fraction of the side each
+    let s = side as f32;
+    let corners = corners.map(|(x, y)| (x * s, y * s));
+    warp_to_square(&page, corners, side)
+}
+
+#[test]
+fn fixtures_photographed_at_an_angle_are_solved() {
+    // the page seen from below and a bit to the left, then turned a little
+    let angles = [
+        [(0.05, 0.0), (0.95, 0.05), (1.0, 1.0), (0.0, 0.95)],
+        [(0.0, 0.08), (1.0, 0.0), (0.92, 1.0), (0.06, 0.93)],
+    ];
+    for (path, solution) in fixtures(UNMARKED) {
+        for corners in angles {
+            let photo = photographed(path, corners);
+            let board = BoardImage::from_photo(photo).unwrap();
+            let colors = board.get_board_colors().unwrap();
+            assert_eq!(queens(&colors).unwrap(), solution, "{} {:?}", path, corners);
+        }
+    }
+}
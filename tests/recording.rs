@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use image::codecs::gif::GifEncoder;
+use image::{Delay, Frame, Rgba, RgbaImage};
+use linkedin_queens::{BoardImage, CellMark, SolutionMarker, draw_solution, process_recording};
+
+// a dark X on the cell, about as much of the middle as the game's own
+fn draw_cross(image: &mut RgbaImage, board: &BoardImage, row: usize, col: usize) {
+    let (cx, cy) = board.cell_center(row, col);
+    let half = (board.cell_size() / 8) as i32;
+    for d in -half..=half {
+        for t in -1..=1 {
+            for (x, y) in [(d + t, d), (d + t, -d)] {
+                image.put_pixel(
+                    (cx as i32 + x) as u32,
+                    (cy as i32 + y) as u32,
+                    Rgba([40, 40, 40, 255]),
+                );
+            }
+        }
+    }
+}
+
+// the empty board, an X on the third cell shown for `cross_ms`, then a crown
+// there, written as a GIF
+fn cross_then_queen(name: &str, cross_ms: u32) -> String {
+    let empty = image::open("image_without_queens.png").unwrap();
+    let board = BoardImage::from_screenshot(empty.clone()).unwrap();
+    let mut cross = empty.to_rgba8();
+    draw_cross(&mut cross, &board, 0, 2);
+    let queen = draw_solution(&empty, &board, &[2], SolutionMarker::Crown);
+
+    let path = std::env::temp_dir().join(format!("queens_{}_{}.gif", name, std::process::id()));
+    let mut encoder = GifEncoder::new_with_speed(std::fs::File::create(&path).unwrap(), 30);
+    let frames = [(empty.to_rgba8(), 500), (cross, cross_ms), (queen, 500)];
+    for (image, ms) in frames {
+        let delay = Delay::from_numer_denom_ms(ms, 1);
+        encoder
+            .encode_frame(Frame::from_parts(image, 0, 0, delay))
+            .unwrap();
+    }
+    drop(encoder);
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn cross_read_mid_animation_is_part_of_the_queen() {
+    let path = cross_then_queen("animated", 100);
+    let recording = process_recording(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(recording.start_frame, 0);
+    assert_eq!(recording.moves.len(), 1, "{:?}", recording.moves);
+    let placed = recording.moves[0];
+    assert_eq!((placed.row, placed.col), (0, 2));
+    assert_eq!((placed.from, placed.to), (CellMark::Empty, CellMark::Queen));
+    assert_eq!(placed.time, Some(Duration::from_millis(600)));
+    assert_eq!(recording.final_queens(), vec![(0, 2)]);
+}
+
+#[test]
+fn cross_that_stays_is_its_own_move() {
+    let path = cross_then_queen("crossed", 1000);
+    let recording = process_recording(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let moves: Vec<(CellMark, CellMark)> = recording.moves.iter().map(|m| (m.from, m.to)).collect();
+    assert_eq!(
+        moves,
+        [
+            (CellMark::Empty, CellMark::Cross),
+            (CellMark::Cross, CellMark::Queen)
+        ]
+    );
+    assert_eq!(recording.moves[0].time, Some(Duration::from_millis(500)));
+    assert_eq!(recording.final_queens(), vec![(0, 2)]);
+}
@@ -0,0 +1,121 @@
+use image::{DynamicImage, Rgba};
+use linkedin_queens::{
+    BoardImage, CellColor, CellMark, RenderOptions, Theme, decode_image, encode_board, parse_board,
+    render_board,
+};
+
+// the two boards saved from the page, 7x7 and 8x8
+fn saved_boards() -> Vec<Vec<Vec<CellColor>>> {
+    [
+        include_str!("../html_board1.html"),
+        include_str!("../html_board2.html"),
+    ]
+    .into_iter()
+    .map(|html| parse_board(html).unwrap())
+    .collect()
+}
+
+// a queen on the diagonal and an X next to each one, like a game in progress
+fn some_marks(n: usize) -> Vec<Vec<CellMark>> {
+    let mut marks = vec![vec![CellMark::Empty; n]; n];
+    for i in (0..n).step_by(3) {
+        marks[i][i] = CellMark::Queen;
+        marks[i][(i + 1) % n] = CellMark::Cross;
+    }
+    marks
+}
+
+// renders, encodes and reads back every saved board with `options`
+fn assert_round_trip(options: &RenderOptions) {
+    for colors in saved_boards() {
+        let marks = some_marks(colors.len());
+        let bytes = encode_board(&colors, Some(&marks), options).unwrap();
+        let board = BoardImage::from_screenshot(decode_image(&bytes).unwrap()).unwrap();
+        let state = board.get_board_state().unwrap();
+        assert_eq!(state.colors, colors, "{:?}", options);
+        assert_eq!(state.marks, marks, "{:?}", options);
+        assert_eq!(board.theme(), options.theme, "{:?}", options);
+    }
+}
+
+#[test]
+fn light_and_dark_read_back() {
+    for theme in [Theme::Light, Theme::Dark] {
+        assert_round_trip(&RenderOptions {
+            theme,
+            ..RenderOptions::default()
+        });
+    }
+}
+
+#[test]
+fn scaled_screenshots_read_back() {
+    for theme in [Theme::Light, Theme::Dark] {
+        for scale in [0.75, 1.5, 2.0] {
+            assert_round_trip(&RenderOptions {
+                scale,
+                theme,
+                ..RenderOptions::default()
+            });
+        }
+    }
+}
+
+#[test]
+fn jpeg_screenshots_read_back() {
+    for theme in [Theme::Light, Theme::Dark] {
+        for (scale, quality) in [(1.0, 90), (1.0, 60), (1.5, 75)] {
+            assert_round_trip(&RenderOptions {
+                scale,
+                theme,
+                jpeg_quality: Some(quality),
+                ..RenderOptions::default()
+            });
+        }
+    }
+}
+
+// the saved boards with every line drawn in `gray` (0 leaves the lines out)
+fn faint_boards(width: u32, gray: u8) -> Vec<(Vec<Vec<CellColor>>, DynamicImage)> {
+    let options = RenderOptions {
+        line_width: width,
+        region_width: width,
+        border_width: width,
+        ..RenderOptions::default()
+    };
+    saved_boards()
+        .into_iter()
+        .map(|colors| {
+            let mut image = render_board(&colors, None, &options).to_rgba8();
+            for pixel in image.pixels_mut() {
+                if *pixel == Rgba([0, 0, 0, 255]) {
+                    *pixel = Rgba([gray, gray, gray, 255]);
+                }
+            }
+            (colors, DynamicImage::ImageRgba8(image))
+        })
+        .collect()
+}
+
+#[test]
+fn faint_lines_are_inferred() {
+    for (colors, image) in faint_boards(1, 200) {
+        let board = BoardImage::from_screenshot(image).unwrap();
+        assert_eq!(board.grid_size(), colors.len());
+        assert_eq!(board.get_board_colors().unwrap(), colors);
+    }
+}
+
+#[test]
+fn boards_without_lines_are_inferred() {
+    for (colors, image) in faint_boards(0, 0) {
+        let board = BoardImage::from_screenshot(image).unwrap();
+        assert_eq!(board.grid_size(), colors.len());
+        // the cells are as wide as they are high, the size they were drawn at
+        let (x0, y0) = board.cell_center(0, 0);
+        let (x1, _) = board.cell_center(0, 1);
+        let (_, y1) = board.cell_center(1, 0);
+        assert!((x1 - x0).abs_diff(72) <= 1 && (y1 - y0).abs_diff(72) <= 1);
+        assert_eq!(board.get_board_colors().unwrap(), colors);
+    }
+}
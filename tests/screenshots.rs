@@ -0,0 +1,241 @@
+mod common;
+
+use common::{FIXTURES, open, solve};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
+use linkedin_queens::{
+    BoardImage, CellMark, SolutionMarker, Theme, background_luma, detect_theme, locate_board,
+    process_image, process_image_state, queens, queens_with_placed, save_solution_image,
+};
+
+// `board` pasted on a page the size of a desktop screen, with a dark header
+// bar and some dark text next to it
+fn full_screenshot(board: &DynamicImage, x: u32, y: u32) -> DynamicImage {
+    let (width, height) = (board.width() + 2 * x, board.height() + y + 150);
+    let mut page = RgbaImage::from_pixel(width, height, Rgba([243, 242, 239, 255]));
+    for py in 0..40 {
+        for px in 0..width {
+            page.put_pixel(px, py, Rgba([29, 34, 38, 255]));
+        }
+    }
+    for line in 0..4 {
+        for py in 0..6 {
+            for px in 20..(x - 40) {
+                page.put_pixel(px, 80 + line * 20 + py, Rgba([40, 40, 40, 255]));
+            }
+        }
+    }
+    page.copy_from(&board.to_rgba8(), x, y).unwrap();
+    DynamicImage::ImageRgba8(page)
+}
+
+// reads an image that's already in memory and solves it
+#[test]
+fn fixtures_are_solved() {
+    for &(path, solution) in FIXTURES {
+        let board = process_image(path).unwrap();
+        assert_eq!(queens(&board).unwrap(), solution, "{}", path);
+    }
+}
+
+#[test]
+fn board_is_found_inside_a_full_screenshot() {
+    for &(path, solution) in FIXTURES {
+        let board = open(path);
+        let (x, y) = (400, 180);
+        let screenshot = full_screenshot(&board, x, y);
+
+        // where it is in the fixture itself (image0.png is a whole desktop
+        // screenshot already), moved by where it was pasted
+        let own = locate_board(&board).unwrap();
+        let region = locate_board(&screenshot).unwrap();
+        assert_eq!((region.x, region.y), (own.x + x, own.y + y), "{}", path);
+        assert_eq!(
+            (region.width, region.height),
+            (own.width, own.height),
+            "{}",
+            path
+        );
+        assert!(
+            own.width >= 580 && own.width.abs_diff(own.height) <= 1,
+            "{} {:?}",
+            path,
+            own
+        );
+
+        let saved = std::env::temp_dir().join(format!("queens_full_{}", path));
+        screenshot.save(&saved).unwrap();
+        let colors = process_image(saved.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&saved).unwrap();
+        assert_eq!(queens(&colors).unwrap(), solution, "{}", path);
+    }
+}
+
+#[test]
+fn antialiased_lines_are_still_found() {
+    // a screenshot taken at another zoom, the 1px lines end up as a blend of
+    // gray over two pixels instead of black
+    for &(path, solution) in FIXTURES {
+        let image = open(path);
+        for scale in [0.7, 0.85] {
+            let (width, height) = (image.width() as f32 * scale, image.height() as f32 * scale);
+            let resized = image.resize_exact(width as u32, height as u32, FilterType::Triangle);
+            assert_eq!(solve(resized), solution, "{} at {}", path, scale);
+        }
+        assert_eq!(solve(image.blur(0.6)), solution, "{} blurred", path);
+    }
+}
+
+#[test]
+fn regions_come_from_the_borders_on_grayscale_screenshots() {
+    for &(path, solution) in FIXTURES {
+        let colored = BoardImage::from_screenshot(open(path)).unwrap();
+        let gray = BoardImage::from_screenshot(open(path).grayscale()).unwrap();
+        let regions = gray.get_board_regions().unwrap();
+        assert_eq!(queens(&regions).unwrap(), solution, "{}", path);
+        // the colors don't matter, the color screenshot has the same borders
+        assert_eq!(regions, colored.get_board_regions().unwrap(), "{}", path);
+    }
+}
+
+#[test]
+fn regions_are_clustered_from_colors_outside_the_palette() {
+    for &(path, solution) in FIXTURES {
+        // every hue moved, none of the colors is in the palette anymore
+        let board = BoardImage::from_screenshot(open(path).huerotate(120)).unwrap();
+        let regions = board.get_color_regions();
+        let n = regions.len();
+        let mut ids: Vec<usize> = regions.iter().flatten().copied().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), n, "{}", path);
+        assert_eq!(queens(&regions).unwrap(), solution, "{}", path);
+    }
+}
+
+#[test]
+fn confidence_drops_on_a_smudged_cell() {
+    for &(path, _) in FIXTURES {
+        let image = open(path);
+        let board = BoardImage::from_screenshot(image.clone()).unwrap();
+        let mid = board.grid_size() / 2;
+        let before = board.read_board().unwrap().cells[mid][mid].confidence;
+        assert!(before > 0.8, "{} {}", path, before);
+
+        // half of the middle cell painted a gray that isn't any region, half of
+        // its samples disagree with the rest
+        let (x, y) = board.cell_center(mid, mid);
+        let half = board.cell_size() / 2;
+        let mut smudged = image.to_rgba8();
+        for py in y - half..y + half {
+            for px in x - half..x {
+                smudged.put_pixel(px, py, Rgba([120, 120, 120, 255]));
+            }
+        }
+        let smudged = BoardImage::from_screenshot(DynamicImage::ImageRgba8(smudged)).unwrap();
+        let reading = smudged.read_board().unwrap();
+        let after = reading.cells[mid][mid].confidence;
+        assert!(after <= 0.6, "{} {}", path, after);
+        // and it's still read as the same region
+        let colors = board.get_board_colors().unwrap();
+        assert_eq!(reading.cells[mid][mid].color, colors[mid][mid], "{}", path);
+    }
+}
+
+#[test]
+fn queens_and_crosses_are_read_from_games_in_progress() {
+    for &(path, solution) in FIXTURES {
+        let state = process_image_state(path).unwrap();
+        let n = state.marks.len();
+        let count = |mark| state.marks.iter().flatten().filter(|&&m| m == mark).count();
+        let queens: Vec<usize> = state
+            .queens()
+            .iter()
+            .map(|&(row, col)| row * n + col)
+            .collect();
+        match path {
+            // finished games, a queen on every solution cell and an X everywhere else
+            "image-1.png" | "image0.png" => {
+                assert_eq!(queens, solution, "{}", path);
+                assert_eq!(count(CellMark::Cross), n * n - n, "{}", path);
+            }
+            "image_with_queens.png" => {
+                assert_eq!(state.queens(), vec![(0, 2), (1, 6)]);
+                assert_eq!(count(CellMark::Cross), 0);
+                let rest = queens_with_placed(&state.colors, &state.queens()).unwrap();
+                assert_eq!(rest, solution);
+            }
+            _ => assert_eq!(count(CellMark::Empty), n * n, "{}", path),
+        }
+    }
+}
+
+#[test]
+fn drawn_solution_lands_on_the_solution_cells() {
+    // the finished games already have their crowns
+    let unfinished = FIXTURES
+        .iter()
+        .filter(|(path, _)| !matches!(*path, "image0.png" | "image-1.png"));
+    for &(path, solution) in unfinished {
+        for marker in [SolutionMarker::Crown, SolutionMarker::Circle] {
+            let output = std::env::temp_dir().join(format!("queens_solved_{:?}_{}", marker, path));
+            let output = output.to_str().unwrap();
+            assert_eq!(save_solution_image(path, output, marker).unwrap(), solution);
+
+            let solved = image::open(output).unwrap();
+            std::fs::remove_file(output).unwrap();
+            assert_eq!(solved.width(), open(path).width(), "{}", path);
+            // read back, every solution cell has a marker on it and nothing else does
+            let state = BoardImage::from_screenshot(solved)
+                .unwrap()
+                .get_board_state()
+                .unwrap();
+            let n = state.marks.len();
+            let marked: Vec<usize> = state
+                .queens()
+                .iter()
+                .map(|&(row, col)| row * n + col)
+                .collect();
+            assert_eq!(marked, solution, "{} {:?}", path, marker);
+        }
+    }
+}
+
+#[test]
+fn faint_lines_are_inferred_on_the_fixtures() {
+    let empty = FIXTURES
+        .iter()
+        .filter(|(path, _)| matches!(*path, "imagez.png" | "image_without_queens.png"));
+    for &(path, solution) in empty {
+        // every line pixel lightened to a pale gray, one by one they're gone
+        let mut image = open(path).to_rgba8();
+        for pixel in image.pixels_mut() {
+            let luma = image::Pixel::to_luma(pixel)[0];
+            if luma < 150 {
+                *pixel = Rgba([200, 200, 200, 255]);
+            }
+        }
+        assert_eq!(solve(DynamicImage::ImageRgba8(image)), solution, "{}", path);
+    }
+}
+
+#[test]
+fn images_too_small_for_a_board_are_an_error() {
+    for side in [0, 1, 10] {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(side, side));
+        assert!(BoardImage::new(image.clone()).is_err(), "{}", side);
+        assert!(
+            BoardImage::from_screenshot(image.clone()).is_err(),
+            "{}",
+            side
+        );
+        assert!(BoardImage::from_photo(image.clone()).is_err(), "{}", side);
+        assert_eq!(locate_board(&image), None, "{}", side);
+    }
+    let empty = DynamicImage::ImageRgba8(RgbaImage::new(0, 0));
+    assert_eq!(background_luma(&empty), None);
+    assert_eq!(detect_theme(&empty), Theme::Light);
+    // a board wide and no pixels high is empty too
+    let line = DynamicImage::ImageRgba8(RgbaImage::new(500, 0));
+    assert!(BoardImage::from_screenshot(line).is_err());
+}
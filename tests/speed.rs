@@ -0,0 +1,43 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{open, solution, solve};
+use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
+
+// imagez.png doubled, like a retina capture, on a 3840x2160 page
+fn screenshot_4k() -> DynamicImage {
+    let board = open("imagez.png");
+    let board = board.resize(
+        board.width() * 2,
+        board.height() * 2,
+        image::imageops::FilterType::Triangle,
+    );
+    let mut page = RgbaImage::from_pixel(3840, 2160, Rgba([243, 242, 239, 255]));
+    page.copy_from(&board.to_rgba8(), 1200, 300).unwrap();
+    DynamicImage::ImageRgba8(page)
+}
+
+#[test]
+fn full_resolution_screenshot_is_solved() {
+    assert_eq!(solve(screenshot_4k()), solution("imagez.png"));
+}
+
+// only means something in release, `cargo test --release -- --ignored`
+#[test]
+#[ignore]
+fn full_resolution_screenshot_takes_under_100ms() {
+    let image = screenshot_4k();
+    // the first run warms up the thread pool
+    solve(image.clone());
+    let mut times: Vec<Duration> = (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            assert_eq!(solve(image.clone()), solution("imagez.png"));
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    println!("Read and solved a 4K screenshot in {:?}", times[2]);
+    assert!(times[2] < Duration::from_millis(100), "{:?}", times);
+}